@@ -4,6 +4,7 @@ use anyhow::Result;
 use ipnet::IpNet;
 
 use crate::{
+    consts,
     message::{AddressMessage, NetlinkRouteAttr},
     request::NetlinkRequestData,
     utils::vec_to_addr,
@@ -35,30 +36,95 @@ pub fn addr_deserialize(buf: &[u8]) -> Result<Address> {
 
     let mut addr = Address {
         index: if_addr_msg.index,
+        flags: if_addr_msg.flags,
         scope: if_addr_msg.scope,
         ..Default::default()
     };
 
+    let mut local = None;
+    let mut dst = None;
+
     for attr in rt_attrs {
         match attr.rt_attr.rta_type {
             libc::IFA_ADDRESS => {
-                addr.ip = IpNet::new(vec_to_addr(attr.value)?, if_addr_msg.prefix_len)?;
+                dst = Some(IpNet::new(
+                    vec_to_addr(attr.value)?,
+                    if_addr_msg.prefix_len,
+                )?);
             }
             libc::IFA_LOCAL => {
-                // TODO
+                local = Some(IpNet::new(
+                    vec_to_addr(attr.value)?,
+                    if_addr_msg.prefix_len,
+                )?);
             }
             libc::IFA_BROADCAST => {
-                // TODO
+                addr.broadcast = Some(vec_to_addr(attr.value)?);
             }
             libc::IFA_LABEL => {
-                // TODO
+                addr.label = String::from_utf8(attr.value)?
+                    .trim_end_matches('\0')
+                    .to_string();
             }
-            libc::IFA_CACHEINFO => {
-                // TODO
+            libc::IFA_CACHEINFO if attr.value.len() >= consts::IFA_CACHEINFO_SIZE => {
+                addr.preferred_lifetime = u32::from_ne_bytes(attr.value[..4].try_into()?) as i32;
+                addr.valid_lifetime = u32::from_ne_bytes(attr.value[4..8].try_into()?) as i32;
             }
             _ => {}
         }
     }
 
+    // on point-to-point links IFA_LOCAL is our own address and IFA_ADDRESS is the peer
+    match (local, dst) {
+        (Some(local), Some(dst)) => {
+            addr.ip = local;
+            if local.addr() != dst.addr() {
+                addr.peer = Some(dst);
+            }
+        }
+        (Some(ip), None) | (None, Some(ip)) => addr.ip = ip,
+        (None, None) => {}
+    }
+
     Ok(addr)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ADDR_MSG: [u8; 72] = [
+        0x02, 0x18, 0x80, 0x00, 0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00, 0x0A, 0xF4, 0x00,
+        0x01, 0x08, 0x00, 0x02, 0x00, 0x0A, 0xF4, 0x00, 0x01, 0x08, 0x00, 0x04, 0x00, 0x0A, 0xF4,
+        0x00, 0xFF, 0x0C, 0x00, 0x03, 0x00, 0x65, 0x74, 0x68, 0x30, 0x3A, 0x31, 0x00, 0x00, 0x08,
+        0x00, 0x08, 0x00, 0x80, 0x00, 0x00, 0x00, 0x14, 0x00, 0x06, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0x2C, 0x01, 0x00, 0x00, 0x2C, 0x01, 0x00, 0x00,
+    ];
+
+    static PTP_ADDR_MSG: [u8; 24] = [
+        0x02, 0x20, 0x80, 0x00, 0x03, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00, 0x0A, 0x00, 0x00,
+        0x02, 0x08, 0x00, 0x02, 0x00, 0x0A, 0x00, 0x00, 0x01,
+    ];
+
+    #[test]
+    fn test_addr_deserialize() {
+        let addr = addr_deserialize(&ADDR_MSG).unwrap();
+
+        assert_eq!(addr.index, 2);
+        assert_eq!(addr.ip, "10.244.0.1/24".parse().unwrap());
+        assert_eq!(addr.label, "eth0:1");
+        assert_eq!(addr.broadcast, Some("10.244.0.255".parse().unwrap()));
+        assert_eq!(addr.peer, None);
+        assert_eq!(addr.preferred_lifetime as u32, u32::MAX);
+        assert_eq!(addr.valid_lifetime as u32, u32::MAX);
+    }
+
+    #[test]
+    fn test_addr_deserialize_ptp() {
+        let addr = addr_deserialize(&PTP_ADDR_MSG).unwrap();
+
+        assert_eq!(addr.index, 3);
+        assert_eq!(addr.ip, "10.0.0.1/32".parse().unwrap());
+        assert_eq!(addr.peer, Some("10.0.0.2/32".parse().unwrap()));
+    }
+}
@@ -6,8 +6,18 @@ pub const NLMSG_DONE: u16 = 3;
 pub const NLMSG_HDRLEN: usize = 0x10;
 
 pub const NLA_F_NESTED: u16 = 0x8000;
+pub const NLM_F_DUMP_INTR: u16 = 0x10;
+pub const NLM_F_CAPPED: u16 = 0x100;
+pub const NLM_F_ACK_TLVS: u16 = 0x200;
+
+pub const NETLINK_EXT_ACK: i32 = 0xB;
+pub const NLMSGERR_ATTR_MSG: u16 = 0x1;
+pub const NLMSGERR_ATTR_OFFS: u16 = 0x2;
+
+pub const DUMP_INTR_RETRIES: usize = 3;
 
 pub const RECV_BUF_SIZE: usize = 65536;
+pub const RECV_TIMEOUT_SECS: u64 = 30;
 pub const PID_KERNEL: u32 = 0;
 
 pub const IFF_UP: u32 = 0x1;
@@ -17,17 +27,52 @@ pub const IFF_LOOPBACK: u32 = 0x8;
 pub const IFF_POINTOPOINT: u32 = 0x10;
 pub const IFF_NOTRAILERS: u32 = 0x20;
 pub const IFF_RUNNING: u32 = 0x40;
+pub const IFF_NOARP: u32 = 0x80;
+pub const IFF_PROMISC: u32 = 0x100;
+pub const IFF_ALLMULTI: u32 = 0x200;
+
+// treat the gateway as directly reachable on the output interface
+pub const RTNH_F_ONLINK: u32 = 0x4;
+
+pub const NDA_MASTER: u16 = 0x9;
+
+pub const RTAX_MTU: u16 = 0x2;
+pub const RTAX_ADVMSS: u16 = 0x8;
+pub const RTAX_HOPLIMIT: u16 = 0xa;
 
 pub const RT_ATTR_SIZE: usize = 0x4;
 pub const IF_INFO_MSG_SIZE: usize = 0x10;
 pub const IF_ADDR_MSG_SIZE: usize = 0x8;
 pub const ROUTE_MSG_SIZE: usize = 0xC;
+pub const NEIGH_MSG_SIZE: usize = 0xC;
+pub const RT_NEXTHOP_SIZE: usize = 0x8;
+pub const IFA_CACHEINFO_SIZE: usize = 0x10;
 
+pub const IFLA_BR_FORWARD_DELAY: u16 = 0x1;
 pub const IFLA_BR_HELLO_TIME: u16 = 0x2;
+pub const IFLA_BR_MAX_AGE: u16 = 0x3;
 pub const IFLA_BR_AGEING_TIME: u16 = 0x4;
+pub const IFLA_BR_STP_STATE: u16 = 0x5;
+pub const IFLA_BR_PRIORITY: u16 = 0x6;
 pub const IFLA_BR_VLAN_FILTERING: u16 = 0x7;
 pub const IFLA_BR_MCAST_SNOOPING: u16 = 0x17;
 
+pub const IFLA_BRPORT_STATE: u16 = 0x1;
+pub const IFLA_BRPORT_PRIORITY: u16 = 0x2;
+pub const IFLA_BRPORT_COST: u16 = 0x3;
+pub const IFLA_BRPORT_MODE: u16 = 0x4;
+pub const IFLA_BRPORT_GUARD: u16 = 0x5;
+pub const IFLA_BRPORT_PROTECT: u16 = 0x6;
+pub const IFLA_BRPORT_FAST_LEAVE: u16 = 0x7;
+pub const IFLA_BRPORT_LEARNING: u16 = 0x8;
+pub const IFLA_BRPORT_UNICAST_FLOOD: u16 = 0x9;
+
+pub const IFLA_BRIDGE_VLAN_INFO: u16 = 0x2;
+
+pub const BRIDGE_VLAN_INFO_MASTER: u16 = 0x1;
+pub const BRIDGE_VLAN_INFO_PVID: u16 = 0x2;
+pub const BRIDGE_VLAN_INFO_UNTAGGED: u16 = 0x4;
+
 pub const IFLA_XDP_FD: u16 = 0x1;
 pub const IFLA_XDP_ATTACHED: u16 = 0x2;
 pub const IFLA_XDP_FLAGS: u16 = 0x3;
@@ -36,3 +81,50 @@ pub const IFLA_XDP_PROG_ID: u16 = 0x4;
 pub const IFLA_GRO_MAX_SIZE: u16 = 0x3a;
 
 pub const VETH_INFO_PEER: u16 = 1;
+
+pub const IFLA_MACVLAN_MODE: u16 = 0x1;
+
+pub const MACVLAN_MODE_PRIVATE: u32 = 0x1;
+pub const MACVLAN_MODE_VEPA: u32 = 0x2;
+pub const MACVLAN_MODE_BRIDGE: u32 = 0x4;
+pub const MACVLAN_MODE_PASSTHRU: u32 = 0x8;
+
+pub const IFLA_VXLAN_ID: u16 = 0x1;
+pub const IFLA_VXLAN_GROUP: u16 = 0x2;
+pub const IFLA_VXLAN_LOCAL: u16 = 0x4;
+pub const IFLA_VXLAN_LEARNING: u16 = 0x7;
+pub const IFLA_VXLAN_PORT: u16 = 0xf;
+pub const IFLA_VXLAN_GROUP6: u16 = 0x10;
+pub const IFLA_VXLAN_LOCAL6: u16 = 0x11;
+
+pub const IFLA_BOND_MODE: u16 = 0x1;
+pub const IFLA_BOND_MIIMON: u16 = 0x3;
+
+pub const BOND_MODE_ROUNDROBIN: u8 = 0x0;
+pub const BOND_MODE_ACTIVEBACKUP: u8 = 0x1;
+pub const BOND_MODE_XOR: u8 = 0x2;
+pub const BOND_MODE_BROADCAST: u8 = 0x3;
+pub const BOND_MODE_8023AD: u8 = 0x4;
+pub const BOND_MODE_TLB: u8 = 0x5;
+pub const BOND_MODE_ALB: u8 = 0x6;
+
+pub const IFLA_TUN_OWNER: u16 = 0x1;
+pub const IFLA_TUN_GROUP: u16 = 0x2;
+pub const IFLA_TUN_TYPE: u16 = 0x3;
+
+pub const TUN_DEVICE_PATH: &str = "/dev/net/tun";
+
+pub const TUNSETIFF: u64 = 0x400454ca;
+pub const TUNSETPERSIST: u64 = 0x400454cb;
+pub const TUNSETOWNER: u64 = 0x400454cc;
+pub const TUNSETGROUP: u64 = 0x400454ce;
+
+pub const IFLA_GRE_IFLAGS: u16 = 0x2;
+pub const IFLA_GRE_OFLAGS: u16 = 0x3;
+pub const IFLA_GRE_IKEY: u16 = 0x4;
+pub const IFLA_GRE_OKEY: u16 = 0x5;
+pub const IFLA_GRE_LOCAL: u16 = 0x6;
+pub const IFLA_GRE_REMOTE: u16 = 0x7;
+pub const IFLA_GRE_TTL: u16 = 0x8;
+
+pub const GRE_KEY: u16 = 0x2000;
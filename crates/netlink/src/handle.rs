@@ -1,4 +1,10 @@
-use std::net::IpAddr;
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    net::IpAddr,
+    os::fd::{AsRawFd, RawFd},
+    time::Duration,
+};
 
 use anyhow::{bail, Result};
 use ipnet::IpNet;
@@ -6,29 +12,97 @@ use ipnet::IpNet;
 use crate::{
     addr::{self, Address},
     consts,
-    link::{self, Kind, Link, LinkAttrs, Namespace},
-    message::{AddressMessage, InfoMessage, NetlinkRouteAttr, RouteMessage},
+    link::{self, BrPortOptions, Kind, Link, LinkAttrs, LinkEvent, Namespace, TuntapMode},
+    message::{
+        AddressMessage, InfoMessage, NeighborMessage, NetlinkMessage, NetlinkRouteAttr,
+        RouteMessage,
+    },
+    neigh::{self, FdbEntry, NeighEvent, Neighbor},
     request::NetlinkRequest,
     route::{self, Route},
     socket::NetlinkSocket,
-    utils::zero_terminated,
+    utils::{align_of, zero_terminated},
 };
 
 pub struct SocketHandle {
+    // starts at a random value and wraps around, see initial_seq
     pub seq: u32,
     pub socket: NetlinkSocket,
 }
 
 impl SocketHandle {
     pub fn new(protocol: i32) -> Result<Self> {
+        Self::with_pid(protocol, 0)
+    }
+
+    // binds the socket to an explicit port id instead of letting the kernel
+    // pick one, replies are matched against whatever id the socket ends up with
+    pub fn with_pid(protocol: i32, pid: u32) -> Result<Self> {
+        let socket = NetlinkSocket::new(protocol, pid, 0)?;
+        socket.set_timeout(Duration::from_secs(consts::RECV_TIMEOUT_SECS))?;
+
         Ok(Self {
-            seq: 0,
-            socket: NetlinkSocket::new(protocol, 0, 0)?,
+            seq: initial_seq(),
+            socket,
         })
     }
 
     pub fn link_new(&mut self, link: &(impl Link + ?Sized), flags: i32) -> Result<()> {
+        let _ = self.link_new_msgs(link, flags, false)?;
+
+        Ok(())
+    }
+
+    // a link that already exists counts as added, it is left untouched
+    pub fn link_add_if_absent(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        match self.link_new(link, flags) {
+            Err(e) if errno(&e) == Some(libc::EEXIST) => Ok(()),
+            res => res,
+        }
+    }
+
+    // creates the link and returns it as echoed back by the kernel, which also
+    // carries the index of a veth peer that may already be in another netns.
+    // kernels that don't echo RTM_NEWLINK get a lookup by name instead, which
+    // still finds the link since nothing has had a chance to move it yet
+    pub fn link_add_get(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        flags: i32,
+    ) -> Result<Box<dyn Link>> {
+        let msgs = self.link_new_msgs(link, flags, true)?;
+
+        match msgs.first() {
+            Some(msg) => link::link_deserialize(msg),
+            None => self.link_get_by_name(&link.attrs().name),
+        }
+    }
+
+    fn link_new_msgs(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mut flags: i32,
+        echo: bool,
+    ) -> Result<Vec<Vec<u8>>> {
         let base = link.attrs();
+
+        // tun/tap devices can't be created over rtnetlink, so create them
+        // through /dev/net/tun first and only apply the rest of the attributes
+        if let Kind::Tuntap {
+            attrs: _,
+            mode,
+            owner,
+            group,
+        } = link.kind()
+        {
+            if flags & libc::NLM_F_CREATE != 0 {
+                tuntap_create(&base.name, *mode, *owner, *group)?;
+                flags &= !(libc::NLM_F_CREATE | libc::NLM_F_EXCL);
+            }
+        }
+
         let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, flags);
         let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
 
@@ -51,14 +125,29 @@ impl SocketHandle {
 
         req.add_data(name);
 
-        // TODO
-        // if base.hw_addr.len() > 0 {
-        //     let hw_addr = Box::new(NetlinkRouteAttr::new(
-        //         libc::IFLA_ADDRESS,
-        //         base.hw_addr.clone(),
-        //     ));
-        //     req.add_data(hw_addr);
-        // }
+        if !base.hw_addr.is_empty() {
+            let hw_addr = Box::new(NetlinkRouteAttr::new(
+                libc::IFLA_ADDRESS,
+                base.hw_addr.clone(),
+            ));
+            req.add_data(hw_addr);
+        }
+
+        if base.parent_index != 0 {
+            let parent_index = Box::new(NetlinkRouteAttr::new(
+                libc::IFLA_LINK,
+                base.parent_index.to_ne_bytes().to_vec(),
+            ));
+            req.add_data(parent_index);
+        }
+
+        if base.master_index != 0 {
+            let master_index = Box::new(NetlinkRouteAttr::new(
+                libc::IFLA_MASTER,
+                base.master_index.to_ne_bytes().to_vec(),
+            ));
+            req.add_data(master_index);
+        }
 
         if base.mtu > 0 {
             let mtu = Box::new(NetlinkRouteAttr::new(
@@ -76,6 +165,14 @@ impl SocketHandle {
             req.add_data(tx_queue_len);
         }
 
+        if base.group > 0 {
+            let group = Box::new(NetlinkRouteAttr::new(
+                libc::IFLA_GROUP,
+                base.group.to_ne_bytes().to_vec(),
+            ));
+            req.add_data(group);
+        }
+
         if base.num_tx_queues > 0 {
             let num_tx_queues = Box::new(NetlinkRouteAttr::new(
                 libc::IFLA_NUM_TX_QUEUES,
@@ -103,6 +200,10 @@ impl SocketHandle {
                 ageing_time,
                 multicast_snooping,
                 vlan_filtering,
+                stp_state,
+                priority,
+                forward_delay,
+                max_age,
             } => {
                 let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
 
@@ -134,6 +235,25 @@ impl SocketHandle {
                     );
                 }
 
+                if let Some(stp_state) = stp_state {
+                    data.add_child(consts::IFLA_BR_STP_STATE, stp_state.to_ne_bytes().to_vec());
+                }
+
+                if let Some(priority) = priority {
+                    data.add_child(consts::IFLA_BR_PRIORITY, priority.to_ne_bytes().to_vec());
+                }
+
+                if let Some(forward_delay) = forward_delay {
+                    data.add_child(
+                        consts::IFLA_BR_FORWARD_DELAY,
+                        forward_delay.to_ne_bytes().to_vec(),
+                    );
+                }
+
+                if let Some(max_age) = max_age {
+                    data.add_child(consts::IFLA_BR_MAX_AGE, max_age.to_ne_bytes().to_vec());
+                }
+
                 link_info.add_child_from_attr(data);
             }
             Kind::Veth {
@@ -141,12 +261,16 @@ impl SocketHandle {
                 peer_name,
                 peer_hw_addr,
                 peer_ns,
+                ..
             } => {
                 let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
                 let mut peer_info = Box::new(NetlinkRouteAttr::new(consts::VETH_INFO_PEER, vec![]));
 
                 peer_info.add_child_from_attr(Box::new(InfoMessage::new(libc::AF_UNSPEC)));
-                peer_info.add_child(libc::IFLA_IFNAME, zero_terminated(peer_name));
+                // without a name the kernel picks one (vethN), like `ip link add type veth`
+                if !peer_name.is_empty() {
+                    peer_info.add_child(libc::IFLA_IFNAME, zero_terminated(peer_name));
+                }
 
                 if base.mtu > 0 {
                     peer_info.add_child(libc::IFLA_MTU, base.mtu.to_ne_bytes().to_vec());
@@ -175,29 +299,128 @@ impl SocketHandle {
                     peer_info.add_child(libc::IFLA_ADDRESS, hw_addr.to_vec());
                 }
 
-                match peer_ns {
-                    Some(ns) => match ns {
+                if let Some(ns) = peer_ns {
+                    match ns {
                         Namespace::Pid(pid) => {
                             peer_info.add_child(libc::IFLA_NET_NS_PID, pid.to_ne_bytes().to_vec());
                         }
                         Namespace::Fd(fd) => {
                             peer_info.add_child(libc::IFLA_NET_NS_FD, fd.to_ne_bytes().to_vec());
                         }
-                    },
-                    None => {}
+                    }
                 }
 
                 data.add_child_from_attr(peer_info);
                 link_info.add_child_from_attr(data);
             }
+            Kind::MacVlan { attrs: _, mode } => {
+                let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+                data.add_child(
+                    consts::IFLA_MACVLAN_MODE,
+                    u32::from(*mode).to_ne_bytes().to_vec(),
+                );
+                link_info.add_child_from_attr(data);
+            }
+            Kind::Vxlan {
+                attrs: _,
+                vni,
+                local,
+                group,
+                dst_port,
+                learning,
+            } => {
+                let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+                data.add_child(consts::IFLA_VXLAN_ID, vni.to_ne_bytes().to_vec());
+
+                match local {
+                    Some(IpAddr::V4(ip)) => {
+                        data.add_child(consts::IFLA_VXLAN_LOCAL, ip.octets().to_vec());
+                    }
+                    Some(IpAddr::V6(ip)) => {
+                        data.add_child(consts::IFLA_VXLAN_LOCAL6, ip.octets().to_vec());
+                    }
+                    None => {}
+                }
+
+                match group {
+                    Some(IpAddr::V4(ip)) => {
+                        data.add_child(consts::IFLA_VXLAN_GROUP, ip.octets().to_vec());
+                    }
+                    Some(IpAddr::V6(ip)) => {
+                        data.add_child(consts::IFLA_VXLAN_GROUP6, ip.octets().to_vec());
+                    }
+                    None => {}
+                }
+
+                if let Some(dst_port) = dst_port {
+                    data.add_child(consts::IFLA_VXLAN_PORT, dst_port.to_be_bytes().to_vec());
+                }
+
+                if let Some(learning) = learning {
+                    data.add_child(
+                        consts::IFLA_VXLAN_LEARNING,
+                        (*learning as u8).to_ne_bytes().to_vec(),
+                    );
+                }
+
+                link_info.add_child_from_attr(data);
+            }
+            Kind::Bond {
+                attrs: _,
+                mode,
+                miimon,
+            } => {
+                let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+                data.add_child(consts::IFLA_BOND_MODE, vec![u8::from(*mode)]);
+
+                if let Some(miimon) = miimon {
+                    data.add_child(consts::IFLA_BOND_MIIMON, miimon.to_ne_bytes().to_vec());
+                }
+
+                link_info.add_child_from_attr(data);
+            }
+            Kind::Gre {
+                attrs: _,
+                local,
+                remote,
+                ttl,
+                key,
+            } => {
+                let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+
+                data.add_child(consts::IFLA_GRE_LOCAL, local.octets().to_vec());
+                data.add_child(consts::IFLA_GRE_REMOTE, remote.octets().to_vec());
+
+                if let Some(ttl) = ttl {
+                    data.add_child(consts::IFLA_GRE_TTL, vec![*ttl]);
+                }
+
+                // the same key is used in both directions, the kernel expects
+                // flags and keys in network byte order
+                if let Some(key) = key {
+                    let flags = consts::GRE_KEY.to_be_bytes().to_vec();
+                    data.add_child(consts::IFLA_GRE_IFLAGS, flags.clone());
+                    data.add_child(consts::IFLA_GRE_OFLAGS, flags);
+                    data.add_child(consts::IFLA_GRE_IKEY, key.to_be_bytes().to_vec());
+                    data.add_child(consts::IFLA_GRE_OKEY, key.to_be_bytes().to_vec());
+                }
+
+                link_info.add_child_from_attr(data);
+            }
             _ => {}
         }
 
-        req.add_data(link_info);
-
-        let _ = self.execute(&mut req, 0)?;
+        // the tun driver rejects any link info sent over rtnetlink
+        if !matches!(link.kind(), Kind::Tuntap { .. }) {
+            req.add_data(link_info);
+        }
 
-        Ok(())
+        match echo {
+            true => self.execute_echo(&mut req, libc::RTM_NEWLINK),
+            false => self.execute(&mut req, 0),
+        }
     }
 
     pub fn link_del(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
@@ -216,6 +439,11 @@ impl SocketHandle {
     }
 
     pub fn link_get(&mut self, attr: &LinkAttrs) -> Result<Box<dyn Link>> {
+        Ok(Box::new(self.link_get_kind(attr)?))
+    }
+
+    // same as link_get, for callers that want to match on the kind directly
+    pub fn link_get_kind(&mut self, attr: &LinkAttrs) -> Result<Kind> {
         let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_ACK);
         let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
 
@@ -237,11 +465,64 @@ impl SocketHandle {
 
         match msgs.len() {
             0 => bail!("no link found"),
-            1 => link::link_deserialize(&msgs[0]),
+            1 => link::link_kind_deserialize(&msgs[0]),
             _ => bail!("multiple links found"),
         }
     }
 
+    pub fn link_get_by_index(&mut self, index: i32) -> Result<Box<dyn Link>> {
+        self.link_get(&LinkAttrs {
+            index,
+            ..Default::default()
+        })
+    }
+
+    pub fn link_get_by_name(&mut self, name: &str) -> Result<Box<dyn Link>> {
+        self.link_get(&LinkAttrs::new().with_name(name))
+    }
+
+    pub fn link_get_master(
+        &mut self,
+        link: &(impl Link + ?Sized),
+    ) -> Result<Option<Box<dyn Link>>> {
+        link.attrs()
+            .master_index()
+            .map(|index| self.link_get_by_index(index))
+            .transpose()
+    }
+
+    pub fn link_list(&mut self) -> Result<Vec<Box<dyn Link>>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        let msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        req.add_data(msg);
+
+        self.execute(&mut req, libc::RTM_NEWLINK)?
+            .iter()
+            .map(|m| link::link_deserialize(m))
+            .collect()
+    }
+
+    pub fn link_list_by_kind(&mut self, kind: &str) -> Result<Vec<Box<dyn Link>>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        let msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        req.add_data(msg);
+
+        // newer kernels filter the dump on the kind, older ones ignore the
+        // attribute so the result is still filtered here
+        let mut link_info = Box::new(NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]));
+        link_info.add_child(libc::IFLA_INFO_KIND, kind.as_bytes().to_vec());
+        req.add_data(link_info);
+
+        Ok(self
+            .execute(&mut req, libc::RTM_NEWLINK)?
+            .iter()
+            .map(|m| link::link_deserialize(m))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|link| link.link_type() == kind)
+            .collect())
+    }
+
     pub fn link_setup(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
         let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
         let base = link.attrs();
@@ -258,420 +539,3067 @@ impl SocketHandle {
         Ok(())
     }
 
-    pub fn addr_handle(
-        &mut self,
-        link: &(impl Link + ?Sized),
-        addr: &Address,
-        proto: u16,
-        flags: i32,
-    ) -> Result<()> {
-        let mut req = NetlinkRequest::new(proto, flags);
+    pub fn link_set_down(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
         let base = link.attrs();
-        let mut index: i32 = base.index;
-
-        if index == 0 {
-            index = match self.link_get(base) {
-                Ok(link) => link.attrs().index,
-                Err(_) => 0,
-            }
-        }
 
-        let (family, local_addr_data) = match addr.ip {
-            IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
-            IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
-        };
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+        msg.flags = 0;
+        msg.change = libc::IFF_UP as u32;
 
-        let peer_addr_data = match addr.peer {
-            Some(IpNet::V4(ip)) if family == libc::AF_INET6 => {
-                ip.addr().to_ipv6_mapped().octets().to_vec()
-            }
-            Some(IpNet::V6(ip)) if family == libc::AF_INET => {
-                // TODO: avoid to use unwrap
-                ip.addr().to_ipv4().unwrap().octets().to_vec()
-            }
-            Some(IpNet::V4(ip)) => ip.addr().octets().to_vec(),
-            Some(IpNet::V6(ip)) => ip.addr().octets().to_vec(),
-            None => local_addr_data.clone(),
-        };
+        req.add_data(msg);
 
-        let msg = Box::new(AddressMessage {
-            family: family as u8,
-            prefix_len: addr.ip.prefix_len(),
-            flags: addr.flags,
-            scope: addr.scope,
-            index,
-        });
+        let _ = self.execute(&mut req, 0)?;
 
-        let local_data = Box::new(NetlinkRouteAttr::new(libc::IFA_LOCAL, local_addr_data));
-        let address_data = Box::new(NetlinkRouteAttr::new(libc::IFA_ADDRESS, peer_addr_data));
+        Ok(())
+    }
 
-        req.add_data(msg);
-        req.add_data(local_data);
-        req.add_data(address_data);
+    pub fn link_set_promisc(&mut self, link: &(impl Link + ?Sized), on: bool) -> Result<()> {
+        self.link_set_flag(link, consts::IFF_PROMISC, on)
+    }
 
-        if family == libc::AF_INET {
-            let broadcast = match addr.broadcast {
-                Some(IpAddr::V4(br)) => br.octets().to_vec(),
-                Some(IpAddr::V6(br)) => br.octets().to_vec(),
-                None => match addr.ip.broadcast() {
-                    IpAddr::V4(br) => br.octets().to_vec(),
-                    IpAddr::V6(br) => br.octets().to_vec(),
-                },
-            };
+    pub fn link_set_allmulti(&mut self, link: &(impl Link + ?Sized), on: bool) -> Result<()> {
+        self.link_set_flag(link, consts::IFF_ALLMULTI, on)
+    }
 
-            let broadcast_data = Box::new(NetlinkRouteAttr::new(libc::IFA_BROADCAST, broadcast));
-            req.add_data(broadcast_data);
+    fn link_set_flag(&mut self, link: &(impl Link + ?Sized), flag: u32, on: bool) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
 
-            if !addr.label.is_empty() {
-                let label_data = Box::new(NetlinkRouteAttr::new(
-                    libc::IFA_LABEL,
-                    zero_terminated(&addr.label),
-                ));
-                req.add_data(label_data);
-            }
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+        msg.flags = if on { flag } else { 0 };
+        msg.change = flag;
 
-            // TODO: add support for IFA_CACHEINFO
-        }
+        req.add_data(msg);
 
         let _ = self.execute(&mut req, 0)?;
 
         Ok(())
     }
 
-    pub fn addr_show(&mut self, link: &(impl Link + ?Sized), family: i32) -> Result<Vec<Address>> {
-        let mut req = NetlinkRequest::new(libc::RTM_GETADDR, libc::NLM_F_DUMP);
-        let msg = Box::new(AddressMessage::new(family));
-        req.add_data(msg);
+    pub fn link_set_mtu(&mut self, link: &(impl Link + ?Sized), mtu: u32) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
 
-        Ok(self
-            .execute(&mut req, libc::RTM_NEWADDR)?
-            .into_iter()
-            .filter_map(|m| addr::addr_deserialize(&m).ok())
-            .filter(|addr| addr.index == link.attrs().index)
-            .collect())
-    }
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
 
-    pub fn route_handle(&mut self, route: &Route, proto: u16, flags: i32) -> Result<()> {
-        let mut req = NetlinkRequest::new(proto, flags);
+        req.add_data(msg);
 
-        let mut msg = match proto {
-            libc::RTM_DELROUTE => Box::new(RouteMessage::new_rt_del_msg()),
-            _ => Box::new(RouteMessage::new_rt_msg()),
-        };
+        let mtu = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MTU,
+            mtu.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(mtu);
 
-        let mut attrs = vec![];
+        let _ = self.execute(&mut req, 0)?;
 
-        if proto != libc::RTM_GETROUTE || route.oif_index > 0 {
-            let mut b = [0; 4];
-            b.copy_from_slice(&route.oif_index.to_ne_bytes());
-            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_OIF, b.to_vec())));
-        }
+        Ok(())
+    }
 
-        if let Some(dst) = route.dst {
-            let (family, dst_data) = match dst {
-                IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
-                IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
-            };
-            msg.family = family as u8;
-            msg.dst_len = dst.prefix_len();
+    pub fn link_set_txqlen(&mut self, link: &(impl Link + ?Sized), len: i32) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
 
-            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data)));
-        }
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
 
-        if let Some(src) = route.src {
-            let (family, src_data) = match src {
-                IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
-                IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
-            };
+        req.add_data(msg);
 
-            if msg.family == 0 {
-                msg.family = family as u8;
-            } else if msg.family != family as u8 {
-                bail!("src and dst address family mismatch");
-            }
+        let tx_queue_len = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_TXQLEN,
+            len.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(tx_queue_len);
 
-            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_PREFSRC, src_data)));
-        }
+        let _ = self.execute(&mut req, 0)?;
 
-        if let Some(gw) = route.gw {
-            let (family, gw_data) = match gw {
-                IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
-                IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
-            };
+        Ok(())
+    }
+
+    pub fn link_set_group(&mut self, link: &(impl Link + ?Sized), group: u32) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        let group = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_GROUP,
+            group.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(group);
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn link_set_alias(&mut self, link: &(impl Link + ?Sized), alias: &str) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        let alias = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_IFALIAS,
+            zero_terminated(alias),
+        ));
+        req.add_data(alias);
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn link_set_master(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        master_index: i32,
+    ) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        let master_index = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_MASTER,
+            master_index.to_ne_bytes().to_vec(),
+        ));
+        req.add_data(master_index);
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    // same as `ip link set dev X nomaster`
+    pub fn link_set_nomaster(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        self.link_set_master(link, 0)
+    }
+
+    pub fn link_set_name(&mut self, link: &(impl Link + ?Sized), name: &str) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        let name = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_IFNAME,
+            zero_terminated(name),
+        ));
+        req.add_data(name);
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn link_set_ns_fd(&mut self, link: &(impl Link + ?Sized), fd: RawFd) -> Result<()> {
+        self.link_set_ns(link, &Namespace::Fd(fd))
+    }
+
+    pub fn link_set_ns_pid(&mut self, link: &(impl Link + ?Sized), pid: i32) -> Result<()> {
+        self.link_set_ns(link, &Namespace::Pid(pid))
+    }
+
+    fn link_set_ns(&mut self, link: &(impl Link + ?Sized), ns: &Namespace) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
 
-            if msg.family == 0 {
-                msg.family = family as u8;
-            } else if msg.family != family as u8 {
-                bail!("gw, src and dst address family mismatch");
+        let mut msg = Box::new(InfoMessage::new(libc::AF_UNSPEC));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        let ns = match ns {
+            Namespace::Pid(pid) => {
+                NetlinkRouteAttr::new(libc::IFLA_NET_NS_PID, pid.to_ne_bytes().to_vec())
+            }
+            Namespace::Fd(fd) => {
+                NetlinkRouteAttr::new(libc::IFLA_NET_NS_FD, fd.to_ne_bytes().to_vec())
             }
+        };
+        req.add_data(Box::new(ns));
 
-            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_GATEWAY, gw_data)));
-        }
+        let _ = self.execute(&mut req, 0)?;
 
-        // TODO: more attributes to be added
+        Ok(())
+    }
 
-        msg.flags = route.flags;
-        msg.scope = route.scope;
+    pub fn link_set_brport(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        opts: &BrPortOptions,
+    ) -> Result<()> {
+        let mut req = NetlinkRequest::new(libc::RTM_SETLINK, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_BRIDGE));
+        msg.index = base.index;
 
         req.add_data(msg);
 
-        for attr in attrs {
-            req.add_data(attr);
+        let mut prot_info = Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_PROTINFO | consts::NLA_F_NESTED,
+            vec![],
+        ));
+
+        if let Some(cost) = opts.cost {
+            prot_info.add_child(consts::IFLA_BRPORT_COST, cost.to_ne_bytes().to_vec());
+        }
+
+        if let Some(priority) = opts.priority {
+            prot_info.add_child(
+                consts::IFLA_BRPORT_PRIORITY,
+                priority.to_ne_bytes().to_vec(),
+            );
+        }
+
+        let flags = [
+            (consts::IFLA_BRPORT_GUARD, opts.guard),
+            (consts::IFLA_BRPORT_MODE, opts.hairpin),
+            (consts::IFLA_BRPORT_FAST_LEAVE, opts.fast_leave),
+            (consts::IFLA_BRPORT_PROTECT, opts.root_block),
+            (consts::IFLA_BRPORT_LEARNING, opts.learning),
+            (consts::IFLA_BRPORT_UNICAST_FLOOD, opts.unicast_flood),
+        ];
+
+        for (rta_type, flag) in flags {
+            if let Some(flag) = flag {
+                prot_info.add_child(rta_type, vec![flag as u8]);
+            }
         }
 
+        req.add_data(prot_info);
+
         let _ = self.execute(&mut req, 0)?;
 
         Ok(())
     }
 
-    pub fn route_get(&mut self, dst: &IpAddr) -> Result<Vec<Route>> {
-        let mut req = NetlinkRequest::new(libc::RTM_GETROUTE, libc::NLM_F_REQUEST);
-        let (family, dst_data, bit_len) = match dst {
-            IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec(), 32),
-            IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec(), 128),
+    pub fn bridge_vlan_add(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        vid: u16,
+        pvid: bool,
+        untagged: bool,
+    ) -> Result<()> {
+        let mut flags = 0;
+
+        if pvid {
+            flags |= consts::BRIDGE_VLAN_INFO_PVID;
+        }
+
+        if untagged {
+            flags |= consts::BRIDGE_VLAN_INFO_UNTAGGED;
+        }
+
+        self.bridge_vlan_handle(libc::RTM_SETLINK, link, vid, flags)
+    }
+
+    pub fn bridge_vlan_del(&mut self, link: &(impl Link + ?Sized), vid: u16) -> Result<()> {
+        self.bridge_vlan_handle(libc::RTM_DELLINK, link, vid, 0)
+    }
+
+    fn bridge_vlan_handle(
+        &mut self,
+        proto: u16,
+        link: &(impl Link + ?Sized),
+        vid: u16,
+        flags: u16,
+    ) -> Result<()> {
+        let mut req = NetlinkRequest::new(proto, libc::NLM_F_ACK);
+        let base = link.attrs();
+
+        let mut msg = Box::new(InfoMessage::new(libc::AF_BRIDGE));
+        msg.index = base.index;
+
+        req.add_data(msg);
+
+        // struct bridge_vlan_info { __u16 flags; __u16 vid; }
+        let mut vlan_info = flags.to_ne_bytes().to_vec();
+        vlan_info.extend_from_slice(&vid.to_ne_bytes());
+
+        let mut af_spec = Box::new(NetlinkRouteAttr::new(libc::IFLA_AF_SPEC, vec![]));
+        af_spec.add_child(consts::IFLA_BRIDGE_VLAN_INFO, vlan_info);
+
+        req.add_data(af_spec);
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn addr_handle(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        addr: &Address,
+        proto: u16,
+        flags: i32,
+    ) -> Result<()> {
+        let mut req = NetlinkRequest::new(proto, flags);
+        let base = link.attrs();
+        let mut index: i32 = base.index;
+
+        if index == 0 {
+            index = match self.link_get(base) {
+                Ok(link) => link.attrs().index,
+                Err(_) => 0,
+            }
+        }
+
+        let (family, local_addr_data) = match addr.ip {
+            IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
+            IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
         };
 
-        let mut msg = Box::new(RouteMessage {
-            ..Default::default()
+        let peer_addr_data = match addr.peer {
+            Some(IpNet::V4(ip)) if family == libc::AF_INET6 => {
+                ip.addr().to_ipv6_mapped().octets().to_vec()
+            }
+            Some(IpNet::V6(ip)) if family == libc::AF_INET => {
+                // TODO: avoid to use unwrap
+                ip.addr().to_ipv4().unwrap().octets().to_vec()
+            }
+            Some(IpNet::V4(ip)) => ip.addr().octets().to_vec(),
+            Some(IpNet::V6(ip)) => ip.addr().octets().to_vec(),
+            None => local_addr_data.clone(),
+        };
+
+        let msg = Box::new(AddressMessage {
+            family: family as u8,
+            prefix_len: addr.ip.prefix_len(),
+            flags: addr.flags,
+            scope: addr.scope,
+            index,
         });
 
-        msg.family = family as u8;
-        msg.dst_len = bit_len;
-        msg.flags = libc::RTM_F_LOOKUP_TABLE;
+        let local_data = Box::new(NetlinkRouteAttr::new(libc::IFA_LOCAL, local_addr_data));
+        let address_data = Box::new(NetlinkRouteAttr::new(libc::IFA_ADDRESS, peer_addr_data));
+
+        req.add_data(msg);
+        req.add_data(local_data);
+        req.add_data(address_data);
+
+        if family == libc::AF_INET {
+            let broadcast = match addr.broadcast {
+                Some(IpAddr::V4(br)) => br.octets().to_vec(),
+                Some(IpAddr::V6(br)) => br.octets().to_vec(),
+                None => match addr.ip.broadcast() {
+                    IpAddr::V4(br) => br.octets().to_vec(),
+                    IpAddr::V6(br) => br.octets().to_vec(),
+                },
+            };
+
+            let broadcast_data = Box::new(NetlinkRouteAttr::new(libc::IFA_BROADCAST, broadcast));
+            req.add_data(broadcast_data);
+
+            if !addr.label.is_empty() {
+                let label_data = Box::new(NetlinkRouteAttr::new(
+                    libc::IFA_LABEL,
+                    zero_terminated(&addr.label),
+                ));
+                req.add_data(label_data);
+            }
+        }
+
+        if addr.preferred_lifetime != 0 || addr.valid_lifetime != 0 {
+            let mut cache_info = Vec::with_capacity(consts::IFA_CACHEINFO_SIZE);
+            cache_info.extend_from_slice(&(addr.preferred_lifetime as u32).to_ne_bytes());
+            cache_info.extend_from_slice(&(addr.valid_lifetime as u32).to_ne_bytes());
+            cache_info.resize(consts::IFA_CACHEINFO_SIZE, 0);
+
+            req.add_data(Box::new(NetlinkRouteAttr::new(
+                libc::IFA_CACHEINFO,
+                cache_info,
+            )));
+        }
+
+        let _ = self.execute(&mut req, 0)?;
 
-        let rta_dst = Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data));
+        Ok(())
+    }
 
+    pub fn addr_show(&mut self, link: &(impl Link + ?Sized), family: i32) -> Result<Vec<Address>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETADDR, libc::NLM_F_DUMP);
+        let msg = Box::new(AddressMessage::new(family));
         req.add_data(msg);
-        req.add_data(rta_dst);
 
         Ok(self
-            .execute(&mut req, libc::RTM_NEWROUTE)?
+            .execute(&mut req, libc::RTM_NEWADDR)?
             .into_iter()
-            .filter_map(|m| route::route_deserialize(&m).ok())
+            .filter_map(|m| addr::addr_deserialize(&m).ok())
+            .filter(|addr| addr.index == link.attrs().index)
+            // the kernel ignores the family of the request for some dumps
+            .filter(|addr| match family {
+                libc::AF_INET => addr.ip.addr().is_ipv4(),
+                libc::AF_INET6 => addr.ip.addr().is_ipv6(),
+                _ => true,
+            })
             .collect())
     }
 
-    fn execute(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<Vec<u8>>> {
-        req.header.nlmsg_seq = {
-            self.seq += 1;
-            self.seq
+    pub fn route_handle(&mut self, route: &Route, proto: u16, flags: i32) -> Result<()> {
+        let mut req = NetlinkRequest::new(proto, flags);
+
+        let mut msg = match proto {
+            libc::RTM_DELROUTE => Box::new(RouteMessage::new_rt_del_msg()),
+            _ => Box::new(RouteMessage::new_rt_msg()),
         };
 
-        let buf = req.serialize()?;
+        msg.family = route.address_family()?;
 
-        self.socket.send(&buf)?;
+        let mut attrs = vec![];
+
+        if proto != libc::RTM_GETROUTE || route.oif_index > 0 {
+            let mut b = [0; 4];
+            b.copy_from_slice(&route.oif_index.to_ne_bytes());
+            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_OIF, b.to_vec())));
+        }
+
+        if route.iif_index > 0 {
+            attrs.push(Box::new(NetlinkRouteAttr::new(
+                libc::RTA_IIF,
+                route.iif_index.to_ne_bytes().to_vec(),
+            )));
+        }
+
+        if let Some(dst) = route.dst {
+            let dst_data = match dst {
+                IpNet::V4(ip) => ip.addr().octets().to_vec(),
+                IpNet::V6(ip) => ip.addr().octets().to_vec(),
+            };
+            msg.dst_len = dst.prefix_len();
+
+            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data)));
+        }
+
+        if let Some(src) = route.src {
+            let src_data = match src {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+
+            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_PREFSRC, src_data)));
+        }
+
+        if let Some(gw) = route.gw {
+            let gw_data = match gw {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+
+            attrs.push(Box::new(NetlinkRouteAttr::new(libc::RTA_GATEWAY, gw_data)));
+        }
+
+        if !route.multipath.is_empty() {
+            let mut multipath = Vec::new();
+
+            for hop in &route.multipath {
+                multipath.extend_from_slice(&hop.serialize()?);
+            }
+
+            attrs.push(Box::new(NetlinkRouteAttr::new(
+                libc::RTA_MULTIPATH,
+                multipath,
+            )));
+        }
+
+        if route.table > 0 {
+            if route.table < 256 {
+                msg.table = route.table as u8;
+            } else {
+                msg.table = libc::RT_TABLE_UNSPEC;
+            }
+
+            attrs.push(Box::new(NetlinkRouteAttr::new(
+                libc::RTA_TABLE,
+                route.table.to_ne_bytes().to_vec(),
+            )));
+        }
+
+        if let Some(metrics) = route.metrics.to_attr() {
+            attrs.push(Box::new(metrics));
+        }
+
+        if let Some(priority) = route.priority {
+            attrs.push(Box::new(NetlinkRouteAttr::new(
+                libc::RTA_PRIORITY,
+                priority.to_ne_bytes().to_vec(),
+            )));
+        }
+
+        // TODO: more attributes to be added
+
+        msg.flags = route.flags;
+        msg.scope = route.scope;
+
+        if route.onlink {
+            msg.flags |= consts::RTNH_F_ONLINK;
+        }
+
+        req.add_data(msg);
+
+        for attr in attrs {
+            req.add_data(attr);
+        }
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn route_list(&mut self, family: i32) -> Result<Vec<Route>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETROUTE, libc::NLM_F_DUMP);
+        req.add_data(Box::new(RouteMessage {
+            family: family as u8,
+            ..Default::default()
+        }));
+
+        Ok(self
+            .execute(&mut req, libc::RTM_NEWROUTE)?
+            .into_iter()
+            .filter_map(|m| route::route_deserialize(&m).ok())
+            .collect())
+    }
+
+    // resolves the single route the kernel would pick for dst, like `ip route get`
+    pub fn route_get_to(&mut self, dst: IpAddr) -> Result<Route> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETROUTE, libc::NLM_F_REQUEST);
+        let (family, dst_data, bit_len) = route_dst(&dst);
+
+        let msg = Box::new(RouteMessage {
+            family: family as u8,
+            dst_len: bit_len,
+            flags: libc::RTM_F_LOOKUP_TABLE,
+            ..Default::default()
+        });
+
+        req.add_data(msg);
+        req.add_data(Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data)));
+
+        match self.execute(&mut req, libc::RTM_NEWROUTE)?.first() {
+            Some(m) => route::route_deserialize(m),
+            None => bail!("no route to {}", dst),
+        }
+    }
+
+    // every route in any table whose destination covers dst, in the order the
+    // kernel dumps them; the kernel doesn't filter dumps on RTA_DST, so the
+    // replies are narrowed down here
+    pub fn route_get(&mut self, dst: &IpAddr) -> Result<Vec<Route>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETROUTE, libc::NLM_F_DUMP);
+        let (family, dst_data, _) = route_dst(dst);
+
+        let msg = Box::new(RouteMessage {
+            family: family as u8,
+            ..Default::default()
+        });
+
+        req.add_data(msg);
+        req.add_data(Box::new(NetlinkRouteAttr::new(libc::RTA_DST, dst_data)));
+
+        let routes = self
+            .execute(&mut req, libc::RTM_NEWROUTE)?
+            .into_iter()
+            .filter_map(|m| route::route_deserialize(&m).ok())
+            .filter(|route| match route.dst {
+                Some(net) => net.contains(dst),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        if routes.is_empty() {
+            bail!("no route to {}", dst);
+        }
+
+        Ok(routes)
+    }
+
+    pub fn neigh_add(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.neigh_handle(
+            neigh,
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )
+    }
+
+    pub fn neigh_del(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.neigh_handle(neigh, libc::RTM_DELNEIGH, libc::NLM_F_ACK)
+    }
+
+    fn neigh_handle(&mut self, neigh: &Neighbor, proto: u16, flags: i32) -> Result<()> {
+        let mut req = NetlinkRequest::new(proto, flags);
+
+        let (family, ip_data) = match neigh.ip {
+            Some(IpAddr::V4(ip)) => (libc::AF_INET as u8, Some(ip.octets().to_vec())),
+            Some(IpAddr::V6(ip)) => (libc::AF_INET6 as u8, Some(ip.octets().to_vec())),
+            None => (neigh.family, None),
+        };
+
+        let msg = Box::new(NeighborMessage {
+            family,
+            index: neigh.index,
+            state: neigh.state,
+            flags: neigh.flags,
+            neigh_type: neigh.neigh_type,
+            ..Default::default()
+        });
+
+        req.add_data(msg);
+
+        if let Some(ip_data) = ip_data {
+            req.add_data(Box::new(NetlinkRouteAttr::new(libc::NDA_DST, ip_data)));
+        }
+
+        if !neigh.mac.is_empty() {
+            req.add_data(Box::new(NetlinkRouteAttr::new(
+                libc::NDA_LLADDR,
+                neigh.mac.clone(),
+            )));
+        }
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn neigh_list(&mut self, index: i32, family: i32) -> Result<Vec<Neighbor>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETNEIGH, libc::NLM_F_DUMP);
+        let msg = Box::new(NeighborMessage::new(family));
+        req.add_data(msg);
+
+        Ok(self
+            .execute(&mut req, libc::RTM_NEWNEIGH)?
+            .into_iter()
+            .filter_map(|m| neigh::neigh_deserialize(&m).ok())
+            .filter(|neigh| index == 0 || neigh.index == index)
+            .collect())
+    }
+
+    // adds a static entry, programmed on the device itself when a remote dst
+    // is given (e.g. vxlan) and on the bridge the port is enslaved to otherwise
+    pub fn fdb_add(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mac: [u8; 6],
+        dst: Option<IpAddr>,
+        vlan: Option<u16>,
+    ) -> Result<()> {
+        self.fdb_handle(
+            link,
+            mac,
+            dst,
+            vlan,
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )
+    }
+
+    pub fn fdb_del(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mac: [u8; 6],
+        dst: Option<IpAddr>,
+        vlan: Option<u16>,
+    ) -> Result<()> {
+        self.fdb_handle(link, mac, dst, vlan, libc::RTM_DELNEIGH, libc::NLM_F_ACK)
+    }
+
+    fn fdb_handle(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mac: [u8; 6],
+        dst: Option<IpAddr>,
+        vlan: Option<u16>,
+        proto: u16,
+        flags: i32,
+    ) -> Result<()> {
+        let mut req = NetlinkRequest::new(proto, flags);
+
+        let msg = Box::new(NeighborMessage {
+            family: libc::AF_BRIDGE as u8,
+            index: link.attrs().index,
+            state: libc::NUD_NOARP,
+            flags: match dst {
+                Some(_) => libc::NTF_SELF,
+                None => libc::NTF_MASTER,
+            },
+            ..Default::default()
+        });
+
+        req.add_data(msg);
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::NDA_LLADDR,
+            mac.to_vec(),
+        )));
+
+        if let Some(dst) = dst {
+            let dst_data = match dst {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+            req.add_data(Box::new(NetlinkRouteAttr::new(libc::NDA_DST, dst_data)));
+        }
+
+        if let Some(vlan) = vlan {
+            req.add_data(Box::new(NetlinkRouteAttr::new(
+                libc::NDA_VLAN,
+                vlan.to_ne_bytes().to_vec(),
+            )));
+        }
+
+        let _ = self.execute(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn fdb_list(&mut self, link: &(impl Link + ?Sized)) -> Result<Vec<FdbEntry>> {
+        let mut req = NetlinkRequest::new(libc::RTM_GETNEIGH, libc::NLM_F_DUMP);
+        let msg = Box::new(NeighborMessage::new(libc::AF_BRIDGE));
+        req.add_data(msg);
+
+        Ok(self
+            .execute(&mut req, libc::RTM_NEWNEIGH)?
+            .into_iter()
+            .filter_map(|m| neigh::fdb_deserialize(&m).ok())
+            .filter(|entry| entry.index == link.attrs().index)
+            .collect())
+    }
+
+    // subscriptions listen on a socket of their own bound to the multicast
+    // group, so they don't borrow a handle
+    pub fn link_subscribe() -> Result<Subscription<LinkEvent>> {
+        let socket = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, libc::RTMGRP_LINK as u32)?;
+
+        Ok(Subscription::new(socket, |m| match m.header.nlmsg_type {
+            libc::RTM_NEWLINK => Some(link::link_deserialize(&m.data).map(LinkEvent::New)),
+            libc::RTM_DELLINK => Some(link::link_deserialize(&m.data).map(LinkEvent::Del)),
+            _ => None,
+        }))
+    }
+
+    pub fn neigh_subscribe() -> Result<Subscription<NeighEvent>> {
+        let socket = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, libc::RTMGRP_NEIGH as u32)?;
+
+        Ok(Subscription::new(socket, |m| match m.header.nlmsg_type {
+            libc::RTM_NEWNEIGH => Some(neigh::neigh_deserialize(&m.data).map(NeighEvent::New)),
+            libc::RTM_DELNEIGH => Some(neigh::neigh_deserialize(&m.data).map(NeighEvent::Del)),
+            _ => None,
+        }))
+    }
+
+    pub fn set_timeout(&self, dur: Duration) -> Result<()> {
+        self.socket.set_timeout(dur).map_err(|e| e.into())
+    }
+
+    pub fn set_recv_buffer_size(&self, bytes: usize) -> Result<()> {
+        self.socket
+            .set_recv_buffer_size(bytes)
+            .map_err(|e| e.into())
+    }
+
+    fn execute(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<Vec<u8>>> {
+        // only dumps are safe to send again, anything else may already have
+        // been applied by the kernel
+        let dump = req.header.nlmsg_flags & libc::NLM_F_DUMP as u16 == libc::NLM_F_DUMP as u16;
+        let mut retries = 0;
+
+        loop {
+            match self.execute_once(req, res_type) {
+                Err(e)
+                    if dump && is_dump_interrupted(&e) && retries < consts::DUMP_INTR_RETRIES =>
+                {
+                    retries += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    // asks the kernel to send back what the request created or changed, the
+    // echoed messages of res_type arrive ahead of the ack
+    fn execute_echo(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<Vec<u8>>> {
+        req.header.nlmsg_flags |= libc::NLM_F_ECHO as u16;
+        self.execute(req, res_type)
+    }
+
+    fn execute_once(&mut self, req: &mut NetlinkRequest, res_type: u16) -> Result<Vec<Vec<u8>>> {
+        req.header.nlmsg_seq = {
+            self.seq = self.seq.wrapping_add(1);
+            self.seq
+        };
+
+        // stamp our own port id rather than leaving it to the kernel, replies
+        // are matched against it below
+        let pid = self.socket.pid()?;
+        req.header.nlmsg_pid = pid;
+
+        let buf = req.serialize()?;
+
+        self.socket.send(&buf)?;
+
+        let mut reply = Reply::new(req.header.nlmsg_seq, pid, res_type);
+
+        loop {
+            let (msgs, from) = self.socket.recv()?;
+
+            if from.nl_pid != consts::PID_KERNEL {
+                bail!(
+                    "wrong sender pid: {}, expected: {}",
+                    from.nl_pid,
+                    consts::PID_KERNEL
+                );
+            }
+
+            if reply.feed(msgs)? {
+                return Ok(reply.res);
+            }
+        }
+    }
+}
+
+// family, RTA_DST payload and full prefix length of a route destination
+fn route_dst(dst: &IpAddr) -> (i32, Vec<u8>, u8) {
+    match dst {
+        IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec(), 32),
+        IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec(), 128),
+    }
+}
+
+// like other netlink clients, start from an unpredictable sequence number so
+// that stale replies addressed to an earlier handle can't be mistaken for ours
+fn initial_seq() -> u32 {
+    let mut seq: u32 = 0;
+    let ret = unsafe {
+        libc::getrandom(
+            &mut seq as *mut _ as *mut libc::c_void,
+            std::mem::size_of::<u32>(),
+            libc::GRND_NONBLOCK,
+        )
+    };
+
+    if ret != std::mem::size_of::<u32>() as isize {
+        seq = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+    }
+
+    seq
+}
+
+// collects the replies to a single request, which may span several recv calls
+struct Reply {
+    seq: u32,
+    pid: u32,
+    res_type: u16,
+    interrupted: bool,
+    res: Vec<Vec<u8>>,
+}
+
+impl Reply {
+    fn new(seq: u32, pid: u32, res_type: u16) -> Self {
+        Self {
+            seq,
+            pid,
+            res_type,
+            interrupted: false,
+            res: Vec::new(),
+        }
+    }
+
+    // returns true once the final message of the reply has been seen
+    fn feed(&mut self, msgs: Vec<NetlinkMessage>) -> Result<bool> {
+        for m in msgs {
+            if m.header.nlmsg_seq != self.seq {
+                continue;
+            }
+
+            if m.header.nlmsg_pid != self.pid {
+                continue;
+            }
+
+            if m.header.nlmsg_flags & consts::NLM_F_DUMP_INTR != 0 {
+                self.interrupted = true;
+            }
+
+            match m.header.nlmsg_type {
+                consts::NLMSG_DONE | consts::NLMSG_ERROR => {
+                    let err_no = i32::from_ne_bytes(m.data[0..4].try_into()?);
+
+                    if err_no == 0 {
+                        if self.interrupted {
+                            return Err(DumpInterrupted.into());
+                        }
+
+                        return Ok(true);
+                    }
+
+                    let err_msg = unsafe { std::ffi::CStr::from_ptr(libc::strerror(-err_no)) };
+                    // keep the errno reachable for callers through downcasting
+                    let err = anyhow::Error::new(std::io::Error::from_raw_os_error(-err_no));
+
+                    if let Some(ext_ack) = ext_ack(&m) {
+                        return Err(err.context(format!(
+                            "{} ({}): {}",
+                            err_msg.to_str()?,
+                            -err_no,
+                            ext_ack
+                        )));
+                    }
+
+                    return Err(err.context(format!(
+                        "{} ({}): {:?}",
+                        err_msg.to_str()?,
+                        -err_no,
+                        &m.data[4..]
+                    )));
+                }
+                t if self.res_type != 0 && t != self.res_type => {
+                    continue;
+                }
+                _ => {
+                    self.res.push(m.data);
+                }
+            }
+
+            if m.header.nlmsg_flags & libc::NLM_F_MULTI as u16 == 0 {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+// extracts the kernel's extended ack message and the offset of the offending attribute
+fn ext_ack(m: &NetlinkMessage) -> Option<String> {
+    if m.header.nlmsg_type != consts::NLMSG_ERROR
+        || m.header.nlmsg_flags & consts::NLM_F_ACK_TLVS == 0
+    {
+        return None;
+    }
+
+    // the error code is followed by the original request, which is only
+    // its header if the reply was capped
+    let offset = if m.header.nlmsg_flags & consts::NLM_F_CAPPED != 0 {
+        consts::NLMSG_HDRLEN
+    } else {
+        let len = u32::from_ne_bytes(m.data.get(4..8)?.try_into().ok()?) as usize;
+        align_of(len, consts::NLMSG_ALIGNTO)
+    };
+
+    let attrs = NetlinkRouteAttr::map(m.data.get(4 + offset..)?).ok()?;
+    let msg = attrs.get(&consts::NLMSGERR_ATTR_MSG).map(|v| {
+        String::from_utf8_lossy(v)
+            .trim_end_matches('\0')
+            .to_string()
+    })?;
+
+    match attrs.get(&consts::NLMSGERR_ATTR_OFFS) {
+        Some(offs) if offs.len() >= 4 => Some(format!(
+            "{} (attribute offset: {})",
+            msg,
+            u32::from_ne_bytes(offs[..4].try_into().ok()?)
+        )),
+        _ => Some(msg),
+    }
+}
+
+// returns the errno of an error reported by the kernel
+pub(crate) fn errno(err: &anyhow::Error) -> Option<i32> {
+    err.downcast_ref::<std::io::Error>()
+        .and_then(|e| e.raw_os_error())
+}
+
+// the kernel flagged a dump with NLM_F_DUMP_INTR because the table changed
+// while it was being read, so the result may be inconsistent
+#[derive(Debug)]
+struct DumpInterrupted;
+
+impl std::fmt::Display for DumpInterrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "netlink dump was interrupted")
+    }
+}
+
+impl std::error::Error for DumpInterrupted {}
+
+fn is_dump_interrupted(err: &anyhow::Error) -> bool {
+    err.is::<DumpInterrupted>()
+}
+
+pub struct Subscription<T> {
+    socket: NetlinkSocket,
+    msgs: VecDeque<NetlinkMessage>,
+    parse: fn(&NetlinkMessage) -> Option<Result<T>>,
+}
+
+impl<T> Subscription<T> {
+    fn new(socket: NetlinkSocket, parse: fn(&NetlinkMessage) -> Option<Result<T>>) -> Self {
+        Self {
+            socket,
+            msgs: VecDeque::new(),
+            parse,
+        }
+    }
+}
+
+impl<T> Iterator for Subscription<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(m) = self.msgs.pop_front() {
+                if let Some(event) = (self.parse)(&m) {
+                    return Some(event);
+                }
+            }
+
+            match self.socket.recv() {
+                Ok((msgs, _)) => self.msgs.extend(msgs),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+#[repr(C)]
+struct IfReq {
+    name: [u8; libc::IFNAMSIZ],
+    flags: libc::c_short,
+    _pad: [u8; 22],
+}
+
+fn tuntap_create(
+    name: &str,
+    mode: TuntapMode,
+    owner: Option<u32>,
+    group: Option<u32>,
+) -> Result<()> {
+    if name.len() >= libc::IFNAMSIZ {
+        bail!("interface name too long: {}", name);
+    }
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(consts::TUN_DEVICE_PATH)?;
+    let fd = file.as_raw_fd();
+
+    let mut req = IfReq {
+        name: [0; libc::IFNAMSIZ],
+        flags: (u8::from(mode) as i32 | libc::IFF_NO_PI) as libc::c_short,
+        _pad: [0; 22],
+    };
+    req.name[..name.len()].copy_from_slice(name.as_bytes());
+
+    let ioctl = |request: u64, arg: libc::c_ulong| -> Result<()> {
+        if unsafe { libc::ioctl(fd, request as _, arg) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    };
+
+    ioctl(consts::TUNSETIFF, &mut req as *mut _ as libc::c_ulong)?;
+
+    if let Some(owner) = owner {
+        ioctl(consts::TUNSETOWNER, owner as libc::c_ulong)?;
+    }
+
+    if let Some(group) = group {
+        ioctl(consts::TUNSETGROUP, group as libc::c_ulong)?;
+    }
+
+    ioctl(consts::TUNSETPERSIST, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::AsRawFd;
+
+    use crate::{
+        addr, consts,
+        link::{
+            self, BondMode, BrPortOptions, Kind, LinkAttrs, LinkEvent, MacVlanMode, Namespace,
+            OperState, TuntapMode,
+        },
+        message::{InfoMessage, NetlinkMessage, NetlinkMessageHeader, NetlinkRouteAttr},
+        neigh::{NeighEvent, Neighbor},
+        request::NetlinkRequest,
+        route::{NextHop, Route, RouteMetrics},
+        utils::zero_terminated,
+    };
+
+    macro_rules! test_setup {
+        () => {
+            if !nix::unistd::geteuid().is_root() {
+                eprintln!("Test skipped, must be run as root");
+                return;
+            }
+            nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWNET).unwrap();
+        };
+    }
+
+    #[test]
+    fn test_initial_seq() {
+        let a = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let b = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        assert_ne!(a.seq, b.seq);
+    }
+
+    #[test]
+    fn test_reply_dump_interrupted() {
+        let msg = |nlmsg_type: u16, nlmsg_flags: u16, data: Vec<u8>| NetlinkMessage {
+            header: NetlinkMessageHeader {
+                nlmsg_len: (consts::NLMSG_HDRLEN + data.len()) as u32,
+                nlmsg_type,
+                nlmsg_flags,
+                nlmsg_seq: 1,
+                nlmsg_pid: 100,
+            },
+            data,
+        };
+        let multi = libc::NLM_F_MULTI as u16;
+
+        let mut reply = super::Reply::new(1, 100, libc::RTM_NEWLINK);
+        let done = reply
+            .feed(vec![
+                msg(libc::RTM_NEWLINK, multi, vec![0; 16]),
+                msg(libc::RTM_NEWLINK, multi, vec![0; 16]),
+                msg(consts::NLMSG_DONE, multi, vec![0; 4]),
+            ])
+            .unwrap();
+        assert!(done);
+        assert_eq!(reply.res.len(), 2);
+
+        let mut reply = super::Reply::new(1, 100, libc::RTM_NEWLINK);
+        let err = reply
+            .feed(vec![
+                msg(libc::RTM_NEWLINK, multi, vec![0; 16]),
+                msg(
+                    libc::RTM_NEWLINK,
+                    multi | consts::NLM_F_DUMP_INTR,
+                    vec![0; 16],
+                ),
+                msg(consts::NLMSG_DONE, multi, vec![0; 4]),
+            ])
+            .err()
+            .unwrap();
+        assert!(super::is_dump_interrupted(&err));
+
+        // a plain EINTR from the kernel must not be mistaken for an interrupted dump
+        let mut reply = super::Reply::new(1, 100, 0);
+        let err = reply
+            .feed(vec![msg(
+                consts::NLMSG_ERROR,
+                0,
+                (-libc::EINTR).to_ne_bytes().to_vec(),
+            )])
+            .err()
+            .unwrap();
+        assert_eq!(super::errno(&err), Some(libc::EINTR));
+        assert!(!super::is_dump_interrupted(&err));
+    }
+
+    #[test]
+    fn test_ext_ack() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let mut req = NetlinkRequest::new(
+            libc::RTM_NEWLINK,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+        req.add_data(Box::new(InfoMessage::new(libc::AF_UNSPEC)));
+        req.add_data(Box::new(NetlinkRouteAttr::new(
+            libc::IFLA_IFNAME,
+            zero_terminated("foo"),
+        )));
+
+        // IFLA_BR_HELLO_TIME must be a u32, send a single byte instead
+        let mut link_info = Box::new(NetlinkRouteAttr::new(libc::IFLA_LINKINFO, vec![]));
+        link_info.add_child(libc::IFLA_INFO_KIND, "bridge".as_bytes().to_vec());
+        let mut data = Box::new(NetlinkRouteAttr::new(libc::IFLA_INFO_DATA, vec![]));
+        data.add_child(consts::IFLA_BR_HELLO_TIME, vec![1]);
+        link_info.add_child_from_attr(data);
+        req.add_data(link_info);
+
+        let err = handle.execute(&mut req, 0).err().unwrap().to_string();
+        assert!(err.contains("failed policy validation"));
+        assert!(err.contains("attribute offset"));
+    }
+
+    #[test]
+    fn test_link_add_modify_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let link = Kind::Dummy(attr.clone());
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().name, "foo");
+
+        attr = link.attrs().clone();
+        attr.name = "bar".to_string();
+
+        let link = Kind::Dummy(attr.clone());
+
+        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().name, "bar");
+
+        handle.link_del(&link).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        println!("{res:?}");
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_hw_addr() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.set_hw_addr([0x02, 0x42, 0xac, 0x11, 0x00, 0x02]);
+
+        let link = Kind::Dummy(attr.clone());
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(
+            link.attrs().hw_addr,
+            vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02]
+        );
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_bridge() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let mut link = Kind::bridge(attr.clone());
+        if let Kind::Bridge {
+            ageing_time,
+            vlan_filtering,
+            ..
+        } = &mut link
+        {
+            *ageing_time = Some(30102);
+            *vlan_filtering = Some(true);
+        }
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "bridge");
+        assert_eq!(link.attrs().name, "foo");
+
+        match link.kind() {
+            Kind::Bridge {
+                attrs: _,
+                hello_time,
+                ageing_time,
+                multicast_snooping,
+                vlan_filtering,
+                ..
+            } => {
+                assert_eq!(hello_time.unwrap(), 200);
+                assert_eq!(ageing_time.unwrap(), 30102);
+                assert!(multicast_snooping.unwrap());
+                assert!(vlan_filtering.unwrap());
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_bridge_stp() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        // times are in hundredths of a second
+        let link = Kind::Bridge {
+            attrs: attr.clone(),
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: Some(1),
+            priority: Some(4096),
+            forward_delay: Some(1000),
+            max_age: Some(1500),
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        match link.kind() {
+            Kind::Bridge {
+                stp_state,
+                priority,
+                forward_delay,
+                max_age,
+                ..
+            } => {
+                assert_eq!(stp_state.unwrap(), 1);
+                assert_eq!(priority.unwrap(), 4096);
+                assert_eq!(forward_delay.unwrap(), 1000);
+                assert_eq!(max_age.unwrap(), 1500);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_bridge_vlan_add_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "br0".to_string();
+
+        let mut bridge = Kind::bridge(attr.clone());
+        if let Kind::Bridge { vlan_filtering, .. } = &mut bridge {
+            *vlan_filtering = Some(true);
+        }
+
+        handle
+            .link_new(
+                &bridge,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&attr).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.master_index = bridge.attrs().index;
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.bridge_vlan_add(&link, 100, false, false).unwrap();
+
+        let vlans = || {
+            let out = std::process::Command::new("bridge")
+                .args(["vlan", "show", "dev", "foo"])
+                .output()
+                .unwrap();
+            String::from_utf8(out.stdout).unwrap()
+        };
+
+        assert!(vlans().contains("100"));
+
+        handle.bridge_vlan_del(&link, 100).unwrap();
+
+        assert!(!vlans().contains("100"));
+
+        handle.link_del(&link).unwrap();
+        handle.link_del(&bridge).unwrap();
+    }
+
+    #[test]
+    fn test_execute_echo() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let msgs = handle
+            .link_new_msgs(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                true,
+            )
+            .unwrap();
+
+        assert_eq!(msgs.len(), 1);
+
+        let echoed = link::link_deserialize(&msgs[0]).unwrap();
+        let link = handle.link_get(&attr).unwrap();
+
+        assert_eq!(echoed.attrs().name, "foo");
+        assert_eq!(echoed.link_type(), link.link_type());
+        assert_eq!(echoed.attrs().index, link.attrs().index);
+
+        // without echo only the ack comes back
+        attr.name = "bar".to_string();
+
+        let msgs = handle
+            .link_new_msgs(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                false,
+            )
+            .unwrap();
+
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn test_link_add_get() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+            peer_index: None,
+        };
+
+        let created = handle
+            .link_add_get(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        let peer = handle
+            .link_get(&LinkAttrs {
+                name: "bar".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(created.attrs().name, "foo");
+        assert_eq!(created.attrs().index, link.attrs().index);
+
+        match created.kind() {
+            Kind::Veth { peer_index, .. } => {
+                assert_eq!(peer_index.unwrap(), peer.attrs().index);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_veth() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.mtu = 1400;
+        attr.tx_queue_len = 100;
+        attr.num_tx_queues = 4;
+        attr.num_rx_queues = 8;
+
+        // TODO: need to set peer hw addr and peer ns
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+            peer_index: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let peer = handle
+            .link_get(&LinkAttrs {
+                name: "bar".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(link.attrs().link_type, "veth");
+        assert_eq!(link.attrs().name, "foo");
+        assert_eq!(link.attrs().mtu, 1400);
+        assert_eq!(link.attrs().tx_queue_len, 100);
+        assert_eq!(link.attrs().num_tx_queues, 4);
+        assert_eq!(link.attrs().num_rx_queues, 8);
+
+        assert_eq!(peer.attrs().link_type, "veth");
+        assert_eq!(peer.attrs().name, "bar");
+        assert_eq!(peer.attrs().mtu, 1400);
+        assert_eq!(peer.attrs().tx_queue_len, 100);
+        assert_eq!(peer.attrs().num_tx_queues, 4);
+        assert_eq!(peer.attrs().num_rx_queues, 8);
+
+        match link.kind() {
+            Kind::Veth { peer_index, .. } => {
+                assert_eq!(peer_index.unwrap(), peer.attrs().index);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&peer).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_macvlan() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let parent = Kind::Dummy(attr.clone());
+
+        handle
+            .link_new(
+                &parent,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let parent = handle.link_get(&attr).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "bar".to_string();
+        attr.parent_index = parent.attrs().index;
+
+        let link = Kind::MacVlan {
+            attrs: attr.clone(),
+            mode: MacVlanMode::Bridge,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "macvlan");
+        assert_eq!(link.attrs().name, "bar");
+        assert_eq!(link.attrs().parent_index, parent.attrs().index);
+
+        match link.kind() {
+            Kind::MacVlan { attrs: _, mode } => {
+                assert_eq!(*mode, MacVlanMode::Bridge);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_vxlan() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let link = Kind::Vxlan {
+            attrs: attr.clone(),
+            vni: 42,
+            local: Some("10.0.0.1".parse().unwrap()),
+            group: None,
+            dst_port: Some(4789),
+            learning: Some(false),
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "vxlan");
+        assert_eq!(link.attrs().name, "foo");
+
+        match link.kind() {
+            Kind::Vxlan {
+                attrs: _,
+                vni,
+                local,
+                group,
+                dst_port,
+                learning,
+            } => {
+                assert_eq!(*vni, 42);
+                assert_eq!(local.unwrap().to_string(), "10.0.0.1");
+                assert!(group.is_none());
+                assert_eq!(dst_port.unwrap(), 4789);
+                assert!(!learning.unwrap());
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_gre() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let link = Kind::Gre {
+            attrs: attr.clone(),
+            local: "127.0.0.1".parse().unwrap(),
+            remote: "127.0.0.2".parse().unwrap(),
+            ttl: Some(64),
+            key: Some(42),
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "gre");
+
+        match link.kind() {
+            Kind::Gre {
+                attrs: _,
+                local,
+                remote,
+                ttl,
+                key,
+            } => {
+                assert_eq!(local.to_string(), "127.0.0.1");
+                assert_eq!(remote.to_string(), "127.0.0.2");
+                assert_eq!(ttl.unwrap(), 64);
+                assert_eq!(key.unwrap(), 42);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_wireguard() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "wg0".to_string();
+
+        handle
+            .link_new(
+                &Kind::Wireguard {
+                    attrs: attr.clone(),
+                },
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.link_type(), "wireguard");
+        assert!(matches!(link.kind(), Kind::Wireguard { .. }));
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_bond() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "bond0".to_string();
+
+        let link = Kind::Bond {
+            attrs: attr.clone(),
+            mode: BondMode::ActiveBackup,
+            miimon: Some(100),
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bond = handle.link_get(&attr).unwrap();
+        assert_eq!(bond.attrs().link_type, "bond");
+
+        match bond.kind() {
+            Kind::Bond {
+                attrs: _,
+                mode,
+                miimon,
+            } => {
+                assert_eq!(*mode, BondMode::ActiveBackup);
+                assert_eq!(miimon.unwrap(), 100);
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        for name in ["foo", "bar"] {
+            let mut attr = LinkAttrs::new();
+            attr.name = name.to_string();
+
+            handle
+                .link_new(
+                    &Kind::Dummy(attr.clone()),
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+
+            let mut attr = handle.link_get(&attr).unwrap().attrs().clone();
+            attr.master_index = bond.attrs().index;
+
+            handle
+                .link_new(&Kind::Dummy(attr.clone()), libc::NLM_F_ACK)
+                .unwrap();
+
+            let slave = handle.link_get(&attr).unwrap();
+            assert_eq!(slave.attrs().master_index, bond.attrs().index);
+        }
+
+        handle.link_del(&bond).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_tuntap() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.mtu = 1400;
+
+        let link = Kind::Tuntap {
+            attrs: attr.clone(),
+            mode: TuntapMode::Tap,
+            owner: Some(0),
+            group: None,
+        };
+
+        handle
+            .link_new(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "tun");
+        assert_eq!(link.attrs().name, "foo");
+        assert_eq!(link.attrs().mtu, 1400);
+
+        match link.kind() {
+            Kind::Tuntap {
+                attrs: _,
+                mode,
+                owner,
+                group,
+            } => {
+                assert_eq!(*mode, TuntapMode::Tap);
+                assert_eq!(owner.unwrap(), 0);
+                assert!(group.is_none());
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        handle.link_del(&link).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_link_set_down() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_setup(&link).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_ne!(link.attrs().raw_flags & consts::IFF_UP, 0);
+
+        handle.link_set_down(&link).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().raw_flags & consts::IFF_UP, 0);
+        assert_eq!(link.attrs().oper_state(), OperState::Down);
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_promisc() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_promisc(&link, true).unwrap();
+        handle.link_set_allmulti(&link, true).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_ne!(link.attrs().raw_flags & consts::IFF_PROMISC, 0);
+        assert_ne!(link.attrs().raw_flags & consts::IFF_ALLMULTI, 0);
+
+        handle.link_set_promisc(&link, false).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().raw_flags & consts::IFF_PROMISC, 0);
+        assert_ne!(link.attrs().raw_flags & consts::IFF_ALLMULTI, 0);
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_mtu() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_mtu(&link, 9000).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().mtu, 9000);
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_txqlen_group() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_txqlen(&link, 500).unwrap();
+        handle.link_set_group(&link, 42).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().tx_queue_len, 500);
+        assert_eq!(link.attrs().group, 42);
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_alias() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_alias(&link, "default/nginx").unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().alias(), "default/nginx");
+
+        handle.link_del(&link).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_master() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut br_attr = LinkAttrs::new();
+        br_attr.name = "br0".to_string();
+
+        let bridge = Kind::bridge(br_attr.clone());
+
+        handle
+            .link_new(
+                &bridge,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&br_attr).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_master(&link, bridge.attrs().index).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().master_index, bridge.attrs().index);
+
+        handle.link_del(&link).unwrap();
+        handle.link_del(&bridge).unwrap();
+    }
+
+    #[test]
+    fn test_link_get_master() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let br_attr = LinkAttrs::new().with_name("br0");
+
+        let bridge = Kind::bridge(br_attr.clone());
+
+        handle
+            .link_new(
+                &bridge,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&br_attr).unwrap();
+        let attr = LinkAttrs::new().with_name("foo");
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert!(handle.link_get_master(&link).unwrap().is_none());
+
+        handle.link_set_master(&link, bridge.attrs().index).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        let master = handle.link_get_master(&link).unwrap().unwrap();
+
+        assert_eq!(master.attrs().name, "br0");
+        assert_eq!(master.link_type(), "bridge");
+    }
+
+    #[test]
+    fn test_link_set_nomaster() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let br_attr = LinkAttrs::new().with_name("br0");
+
+        let bridge = Kind::bridge(br_attr.clone());
+
+        handle
+            .link_new(
+                &bridge,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&br_attr).unwrap();
+        let attr = LinkAttrs::new().with_name("foo");
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_set_master(&link, bridge.attrs().index).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().master_index, bridge.attrs().index);
+
+        handle.link_set_nomaster(&link).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        assert_eq!(link.attrs().master_index, 0);
+    }
+
+    #[test]
+    fn test_link_set_name() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        handle.link_set_name(&link, "bar").unwrap();
+
+        attr.name = "bar".to_string();
+
+        let renamed = handle.link_get(&attr).unwrap();
+        assert_eq!(renamed.attrs().index, link.attrs().index);
+
+        handle.link_del(&renamed).unwrap();
+    }
+
+    #[test]
+    fn test_link_set_ns_fd() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let ns = std::thread::spawn(|| {
+            nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWNET).unwrap();
+            std::fs::File::open("/proc/thread-self/ns/net").unwrap()
+        })
+        .join()
+        .unwrap();
+
+        handle.link_set_ns_fd(&link, ns.as_raw_fd()).unwrap();
+
+        let res = handle.link_get(&attr).err();
+        assert!(res.is_some());
+    }
+
+    #[test]
+    fn test_namespace_from_path() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let netns = "comet-netlink-test";
+
+        let out = std::process::Command::new("ip")
+            .args(["netns", "add", netns])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+
+        let path = format!("/var/run/netns/{netns}");
+        let (ns, _file) = Namespace::from_path(std::path::Path::new(&path)).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        let res = handle.link_new(
+            &Kind::Veth {
+                attrs: attr.clone(),
+                peer_name: "bar".to_string(),
+                peer_hw_addr: None,
+                peer_ns: Some(ns),
+                peer_index: None,
+            },
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+
+        let peer = handle.link_get(&LinkAttrs {
+            name: "bar".to_string(),
+            ..Default::default()
+        });
+
+        let _ = std::process::Command::new("ip")
+            .args(["netns", "del", netns])
+            .output();
+
+        res.unwrap();
+        assert!(handle.link_get(&attr).is_ok());
+        assert!(peer.is_err());
+        assert!(Namespace::from_path(std::path::Path::new(&path)).is_err());
+    }
+
+    #[test]
+    fn test_link_set_brport() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "br0".to_string();
+
+        let bridge = Kind::bridge(attr.clone());
+
+        handle
+            .link_new(
+                &bridge,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&attr).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.master_index = bridge.attrs().index;
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let opts = BrPortOptions {
+            cost: Some(42),
+            priority: Some(7),
+            guard: Some(true),
+            ..Default::default()
+        };
+
+        handle.link_set_brport(&link, &opts).unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        let prot_info = link.attrs().prot_info.as_ref().unwrap();
+
+        assert_eq!(prot_info.cost.unwrap(), 42);
+        assert_eq!(prot_info.priority.unwrap(), 7);
+        assert!(prot_info.guard.unwrap());
+
+        handle.link_del(&bridge).unwrap();
+    }
+
+    #[test]
+    fn test_route_multipath() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let addr = addr::Address {
+            ip: "10.244.0.1/24".parse().unwrap(),
+            ..Default::default()
+        };
+
+        handle
+            .addr_handle(
+                &link,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let multipath = vec![
+            NextHop {
+                gw: "10.244.0.2".parse().unwrap(),
+                index: link.attrs().index,
+                weight: 1,
+            },
+            NextHop {
+                gw: "10.244.0.3".parse().unwrap(),
+                index: link.attrs().index,
+                weight: 2,
+            },
+        ];
+
+        let route = Route {
+            dst: Some("0.0.0.0/0".parse().unwrap()),
+            multipath: multipath.clone(),
+            ..Default::default()
+        };
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let routes = handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter(|r| !r.multipath.is_empty())
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].multipath, multipath);
+    }
+
+    #[test]
+    fn test_route_table() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            scope: libc::RT_SCOPE_LINK,
+            table: 300,
+            ..Default::default()
+        };
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let routes = handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.table == 300)
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, link.attrs().index);
+        assert_eq!(routes[0].dst, route.dst);
+
+        handle
+            .route_handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap();
+
+        assert!(handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .iter()
+            .all(|r| r.table != 300));
+    }
+
+    #[test]
+    fn test_route_priority() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let addr = addr::Address {
+            ip: "10.244.0.1/24".parse().unwrap(),
+            ..Default::default()
+        };
+
+        handle
+            .addr_handle(
+                &link,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        for (gw, priority) in [("10.244.0.3", 200), ("10.244.0.2", 100)] {
+            let route = Route {
+                oif_index: link.attrs().index,
+                dst: Some("0.0.0.0/0".parse().unwrap()),
+                gw: Some(gw.parse().unwrap()),
+                priority: Some(priority),
+                ..Default::default()
+            };
+
+            handle
+                .route_handle(
+                    &route,
+                    libc::RTM_NEWROUTE,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
+
+        let mut priorities = handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.gw.is_some())
+            .filter_map(|r| r.priority)
+            .collect::<Vec<_>>();
+        priorities.sort();
+        assert_eq!(priorities, vec![100, 200]);
+
+        // both default routes cover the destination, the kernel keeps the
+        // lower metric first
+        let routes = handle.route_get(&"8.8.8.8".parse().unwrap()).unwrap();
+
+        assert_eq!(routes.len(), 2);
+        assert_eq!(routes[0].gw, Some("10.244.0.2".parse().unwrap()));
+        assert_eq!(routes[0].priority, Some(100));
+    }
+
+    #[test]
+    fn test_route_iif() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Veth {
+                    attrs: attr.clone(),
+                    peer_name: "bar".to_string(),
+                    peer_hw_addr: None,
+                    peer_ns: None,
+                    peer_index: None,
+                },
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let mut peer_attr = LinkAttrs::new();
+        peer_attr.name = "bar".to_string();
+        let peer = handle.link_get(&peer_attr).unwrap();
+        handle.link_setup(&peer).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            iif_index: peer.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            scope: libc::RT_SCOPE_LINK,
+            ..Default::default()
+        };
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        // the fib accepts RTA_IIF but doesn't keep it for unicast routes
+        let routes = handle.route_get(&"192.168.0.1".parse().unwrap()).unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, link.attrs().index);
+
+        handle
+            .route_handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_route_onlink() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        // gateways are only classified once loopback is up
+        let mut lo = LinkAttrs::new();
+        lo.name = "lo".to_string();
+        let lo = handle.link_get(&lo).unwrap();
+        handle.link_setup(&lo).unwrap();
+
+        let mut route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            gw: Some("10.244.0.1".parse().unwrap()),
+            ..Default::default()
+        };
+
+        // the gateway isn't on any of the link's subnets
+        assert!(handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .is_err());
+
+        route.onlink = true;
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let routes = handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.dst == route.dst)
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].gw, route.gw);
+        assert!(routes[0].onlink);
+    }
+
+    #[test]
+    fn test_route_get_to() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        assert!(handle.route_get_to("8.8.8.8".parse().unwrap()).is_err());
+
+        let addr = addr::Address {
+            ip: "10.244.0.1/24".parse().unwrap(),
+            ..Default::default()
+        };
+
+        handle
+            .addr_handle(
+                &link,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("0.0.0.0/0".parse().unwrap()),
+            gw: Some("10.244.0.254".parse().unwrap()),
+            ..Default::default()
+        };
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let res = handle.route_get_to("8.8.8.8".parse().unwrap()).unwrap();
+
+        assert_eq!(res.oif_index, link.attrs().index);
+        assert_eq!(res.gw, route.gw);
+        assert_eq!(res.src, Some("10.244.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_route_metrics() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            scope: libc::RT_SCOPE_LINK,
+            metrics: RouteMetrics {
+                mtu: Some(1400),
+                advmss: Some(1360),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        handle
+            .route_handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let routes = handle
+            .route_list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.dst == route.dst)
+            .collect::<Vec<_>>();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].metrics, route.metrics);
+    }
+
+    #[test]
+    fn test_neigh_add_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        let neigh = Neighbor {
+            index: link.attrs().index,
+            ip: Some("10.244.0.2".parse().unwrap()),
+            mac: vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02],
+            state: libc::NUD_PERMANENT,
+            ..Default::default()
+        };
+
+        handle.neigh_add(&neigh).unwrap();
+
+        let neighs = handle
+            .neigh_list(link.attrs().index, libc::AF_INET)
+            .unwrap();
+
+        assert_eq!(neighs.len(), 1);
+        assert_eq!(neighs[0].ip, neigh.ip);
+        assert_eq!(neighs[0].mac, neigh.mac);
+        assert_eq!(neighs[0].state, libc::NUD_PERMANENT);
+
+        handle.neigh_del(&neigh).unwrap();
+
+        let neighs = handle
+            .neigh_list(link.attrs().index, libc::AF_INET)
+            .unwrap();
+        assert!(neighs.is_empty());
+    }
+
+    #[test]
+    fn test_fdb_list() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let bridge = LinkAttrs::new().with_name("br0");
+
+        handle
+            .link_new(
+                &Kind::bridge(bridge.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&bridge).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.master_index = bridge.attrs().index;
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+
+        // the bridge learns the port's own address as a permanent entry
+        let entries = handle.fdb_list(&link).unwrap();
+        let entry = entries
+            .iter()
+            .find(|e| e.mac[..] == *link.attrs().hw_addr())
+            .unwrap();
+
+        assert_eq!(entry.index, link.attrs().index);
+        assert_eq!(entry.master_index, bridge.attrs().index);
+        assert_eq!(entry.state, libc::NUD_PERMANENT);
+    }
+
+    #[test]
+    fn test_fdb_add_del() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let bridge = LinkAttrs::new().with_name("br0");
+
+        handle
+            .link_new(
+                &Kind::bridge(bridge.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let bridge = handle.link_get(&bridge).unwrap();
+
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+        attr.master_index = bridge.attrs().index;
+
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        let mac = [0x02, 0x42, 0x0a, 0xf4, 0x00, 0x02];
+
+        handle.fdb_add(&link, mac, None, None).unwrap();
+
+        let entries = handle.fdb_list(&link).unwrap();
+        let entry = entries.iter().find(|e| e.mac == mac).unwrap();
+
+        assert_eq!(entry.master_index, bridge.attrs().index);
+        assert_eq!(entry.state, libc::NUD_NOARP);
+        assert_eq!(entry.vlan, None);
+
+        handle.fdb_del(&link, mac, None, None).unwrap();
+
+        let entries = handle.fdb_list(&link).unwrap();
+        assert!(entries.iter().all(|e| e.mac != mac));
+    }
+
+    #[test]
+    fn test_link_subscribe() {
+        test_setup!();
+        let mut events = super::SocketHandle::link_subscribe().unwrap();
 
-        let pid = self.socket.pid()?;
-        let mut res: Vec<Vec<u8>> = Vec::new();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
 
-        'done: loop {
-            let (msgs, from) = self.socket.recv()?;
+        let link = Kind::Dummy(attr.clone());
 
-            if from.nl_pid != consts::PID_KERNEL {
-                bail!(
-                    "wrong sender pid: {}, expected: {}",
-                    from.nl_pid,
-                    consts::PID_KERNEL
-                );
-            }
+        std::thread::spawn(move || {
+            let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+            handle
+                .link_new(
+                    &link,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        })
+        .join()
+        .unwrap();
 
-            for m in msgs {
-                if m.header.nlmsg_seq != req.header.nlmsg_seq {
-                    continue;
-                }
+        match events.next().unwrap().unwrap() {
+            LinkEvent::New(link) => assert_eq!(link.attrs().name, "foo"),
+            _ => panic!("expected new link event"),
+        }
+    }
 
-                if m.header.nlmsg_pid != pid {
-                    continue;
-                }
+    #[test]
+    fn test_neigh_subscribe() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
 
-                match m.header.nlmsg_type {
-                    consts::NLMSG_DONE | consts::NLMSG_ERROR => {
-                        let err_no = i32::from_ne_bytes(m.data[0..4].try_into()?);
+        handle
+            .link_new(
+                &Kind::Dummy(attr.clone()),
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
 
-                        if err_no == 0 {
-                            break 'done;
-                        }
+        let link = handle.link_get(&attr).unwrap();
+        let mut events = super::SocketHandle::neigh_subscribe().unwrap();
 
-                        let err_msg = unsafe { std::ffi::CStr::from_ptr(libc::strerror(-err_no)) };
-                        bail!("{} ({}): {:?}", err_msg.to_str()?, -err_no, &m.data[4..]);
-                    }
-                    t if res_type != 0 && t != res_type => {
-                        continue;
-                    }
-                    _ => {
-                        res.push(m.data);
-                    }
-                }
+        let neigh = Neighbor {
+            index: link.attrs().index,
+            ip: Some("10.244.0.2".parse().unwrap()),
+            mac: vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02],
+            state: libc::NUD_PERMANENT,
+            ..Default::default()
+        };
 
-                if m.header.nlmsg_flags & libc::NLM_F_MULTI as u16 == 0 {
-                    break 'done;
-                }
+        let added = neigh.clone();
+        std::thread::spawn(move || {
+            let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+            handle.neigh_add(&added).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        match events.next().unwrap().unwrap() {
+            NeighEvent::New(n) => {
+                assert_eq!(n.index, neigh.index);
+                assert_eq!(n.ip, neigh.ip);
+                assert_eq!(n.mac, neigh.mac);
             }
+            _ => panic!("expected new neighbor event"),
         }
+    }
+
+    #[test]
+    fn test_with_pid() {
+        // port ids are unique per protocol, keep clear of other test processes
+        let pid = 0x4000_0000 | std::process::id() << 8;
+
+        let mut a = super::SocketHandle::with_pid(libc::NETLINK_ROUTE, pid).unwrap();
+        let mut b = super::SocketHandle::with_pid(libc::NETLINK_ROUTE, pid + 1).unwrap();
 
-        Ok(res)
+        assert_eq!(a.socket.pid().unwrap(), pid);
+        assert_eq!(b.socket.pid().unwrap(), pid + 1);
+        assert!(super::SocketHandle::with_pid(libc::NETLINK_ROUTE, pid).is_err());
+
+        for handle in [&mut a, &mut b] {
+            let link = handle.link_get_by_index(1).unwrap();
+            assert_eq!(link.attrs().name, "lo");
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        addr,
-        link::{self, Kind, LinkAttrs},
-        route::Route,
-    };
+    #[test]
+    fn test_execute_sets_pid() {
+        let pid = 0x4000_0080 | std::process::id() << 8;
+        let mut handle = super::SocketHandle::with_pid(libc::NETLINK_ROUTE, pid).unwrap();
+        handle
+            .socket
+            .set_timeout(std::time::Duration::from_secs(1))
+            .unwrap();
 
-    macro_rules! test_setup {
-        () => {
-            if !nix::unistd::geteuid().is_root() {
-                eprintln!("Test skipped, must be run as root");
-                return;
-            }
-            nix::sched::unshare(nix::sched::CloneFlags::CLONE_NEWNET).unwrap();
-        };
+        let mut req = NetlinkRequest::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        req.add_data(Box::new(InfoMessage::new(libc::AF_UNSPEC)));
+
+        let msgs = handle.execute(&mut req, libc::RTM_NEWLINK).unwrap();
+
+        assert_eq!(req.header.nlmsg_pid, pid);
+        assert!(!msgs.is_empty());
     }
 
     #[test]
-    fn test_link_add_modify_del() {
+    fn test_link_list_by_kind() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let mut attr = LinkAttrs::new();
-        attr.name = "foo".to_string();
 
-        let link = Kind::Dummy(attr.clone());
+        for name in ["foo", "bar"] {
+            handle
+                .link_new(
+                    &Kind::Dummy(LinkAttrs::new().with_name(name)),
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
+
+        let bridge = Kind::bridge(LinkAttrs::new().with_name("baz"));
 
         handle
             .link_new(
-                &link,
+                &bridge,
                 libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
             )
             .unwrap();
 
-        let link = handle.link_get(&attr).unwrap();
-        assert_eq!(link.attrs().name, "foo");
-
-        attr = link.attrs().clone();
-        attr.name = "bar".to_string();
+        let links = handle.link_list_by_kind("bridge").unwrap();
 
-        let link = Kind::Dummy(attr.clone());
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].attrs().name, "baz");
+        assert_eq!(links[0].link_type(), "bridge");
+    }
 
-        handle.link_new(&link, libc::NLM_F_ACK).unwrap();
+    #[test]
+    fn test_link_get() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = link::LinkAttrs::new();
+        attr.name = "lo".to_string();
 
         let link = handle.link_get(&attr).unwrap();
-        assert_eq!(link.attrs().name, "bar");
 
-        handle.link_del(&link).unwrap();
+        assert_eq!(link.attrs().index, 1);
+        assert_eq!(link.attrs().name, "lo");
+    }
 
-        let res = handle.link_get(&attr).err();
-        println!("{res:?}");
-        assert!(res.is_some());
+    #[test]
+    fn test_link_get_by_index_name() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+
+        let link = handle.link_get_by_index(1).unwrap();
+        assert_eq!(link.attrs().name, "lo");
+
+        let link = handle.link_get_by_name("lo").unwrap();
+        assert_eq!(link.attrs().index, 1);
+
+        assert!(handle.link_get_by_name("foo").is_err());
     }
 
     #[test]
-    fn test_link_bridge() {
+    fn test_link_get_kind() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let mut attr = LinkAttrs::new();
-        attr.name = "foo".to_string();
+        let attr = LinkAttrs::new().with_name("br0");
 
-        let link = Kind::Bridge {
-            attrs: attr.clone(),
-            hello_time: None,
-            ageing_time: Some(30102),
-            multicast_snooping: None,
-            vlan_filtering: Some(true),
-        };
+        let mut bridge = Kind::bridge(attr.clone());
+        if let Kind::Bridge { stp_state, .. } = &mut bridge {
+            *stp_state = Some(1);
+        }
 
         handle
             .link_new(
-                &link,
+                &bridge,
                 libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
             )
             .unwrap();
 
-        let link = handle.link_get(&attr).unwrap();
-        assert_eq!(link.attrs().link_type, "bridge");
-        assert_eq!(link.attrs().name, "foo");
-
-        match link.kind() {
+        match handle.link_get_kind(&attr).unwrap() {
             Kind::Bridge {
-                attrs: _,
-                hello_time,
-                ageing_time,
-                multicast_snooping,
-                vlan_filtering,
+                attrs, stp_state, ..
             } => {
-                assert_eq!(hello_time.unwrap(), 200);
-                assert_eq!(ageing_time.unwrap(), 30102);
-                assert!(multicast_snooping.unwrap());
-                assert!(vlan_filtering.unwrap());
+                assert_eq!(attrs.name, "br0");
+                assert_eq!(stp_state, Some(1));
             }
             _ => panic!("wrong link type"),
         }
+    }
 
-        handle.link_del(&link).unwrap();
+    #[test]
+    fn test_link_list() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
 
-        let res = handle.link_get(&attr).err();
-        assert!(res.is_some());
+        let links = handle.link_list().unwrap();
+
+        assert!(links
+            .iter()
+            .any(|link| link.attrs().index == 1 && link.attrs().name == "lo"));
     }
 
     #[test]
-    fn test_link_veth() {
+    fn test_addr_handle() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
-        let mut attr = LinkAttrs::new();
-        attr.name = "foo".to_string();
-        attr.mtu = 1400;
-        attr.tx_queue_len = 100;
-        attr.num_tx_queues = 4;
-        attr.num_rx_queues = 8;
+        let mut attr = link::LinkAttrs::new();
+        attr.name = "lo".to_string();
 
-        // TODO: need to set peer hw addr and peer ns
-        let link = Kind::Veth {
-            attrs: attr.clone(),
-            peer_name: "bar".to_string(),
-            peer_hw_addr: None,
-            peer_ns: None,
+        let link = handle.link_get(&attr).unwrap();
+
+        let address = "127.0.0.2/24".parse().unwrap();
+        let addr = addr::Address {
+            ip: address,
+            ..Default::default()
         };
 
+        let proto = libc::RTM_NEWADDR;
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        handle.addr_handle(&link, &addr, proto, flags).unwrap();
+
+        let addrs = handle.addr_show(&link, libc::AF_UNSPEC).unwrap();
+
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].ip, address);
+    }
+
+    #[test]
+    fn test_addr_show_family() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = LinkAttrs::new();
+        attr.name = "foo".to_string();
+
         handle
             .link_new(
-                &link,
+                &Kind::Dummy(attr.clone()),
                 libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
             )
             .unwrap();
 
         let link = handle.link_get(&attr).unwrap();
 
-        let peer = handle
-            .link_get(&LinkAttrs {
-                name: "bar".to_string(),
+        for ip in ["10.244.0.1/24", "fd00::1/64"] {
+            let addr = addr::Address {
+                ip: ip.parse().unwrap(),
                 ..Default::default()
-            })
-            .unwrap();
+            };
 
-        assert_eq!(link.attrs().link_type, "veth");
-        assert_eq!(link.attrs().name, "foo");
-        assert_eq!(link.attrs().mtu, 1400);
-        assert_eq!(link.attrs().tx_queue_len, 100);
-        assert_eq!(link.attrs().num_tx_queues, 4);
-        assert_eq!(link.attrs().num_rx_queues, 8);
+            handle
+                .addr_handle(
+                    &link,
+                    &addr,
+                    libc::RTM_NEWADDR,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
 
-        assert_eq!(peer.attrs().link_type, "veth");
-        assert_eq!(peer.attrs().name, "bar");
-        assert_eq!(peer.attrs().mtu, 1400);
-        assert_eq!(peer.attrs().tx_queue_len, 100);
-        assert_eq!(peer.attrs().num_tx_queues, 4);
-        assert_eq!(peer.attrs().num_rx_queues, 8);
+        let res = handle.addr_show(&link, libc::AF_INET).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].ip.to_string(), "10.244.0.1/24");
 
-        handle.link_del(&peer).unwrap();
+        let res = handle.addr_show(&link, libc::AF_INET6).unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].ip.to_string(), "fd00::1/64");
 
-        let res = handle.link_get(&attr).err();
-        assert!(res.is_some());
+        let res = handle.addr_show(&link, libc::AF_UNSPEC).unwrap();
+        assert_eq!(res.len(), 2);
+
+        handle.link_del(&link).unwrap();
     }
 
     #[test]
-    fn test_link_get() {
+    fn test_addr_lifetime() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
         let mut attr = link::LinkAttrs::new();
@@ -679,12 +3607,31 @@ mod tests {
 
         let link = handle.link_get(&attr).unwrap();
 
-        assert_eq!(link.attrs().index, 1);
-        assert_eq!(link.attrs().name, "lo");
+        let addr = addr::Address {
+            ip: "127.0.0.2/24".parse().unwrap(),
+            preferred_lifetime: 1800,
+            valid_lifetime: 3600,
+            ..Default::default()
+        };
+
+        handle
+            .addr_handle(
+                &link,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let addrs = handle.addr_show(&link, libc::AF_INET).unwrap();
+        let res = addrs.iter().find(|a| a.ip == addr.ip).unwrap();
+
+        assert!(res.valid_lifetime > 0 && res.valid_lifetime <= 3600);
+        assert!(res.preferred_lifetime > 0 && res.preferred_lifetime <= 1800);
     }
 
     #[test]
-    fn test_addr_handle() {
+    fn test_addr_show_large_dump() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
         let mut attr = link::LinkAttrs::new();
@@ -692,21 +3639,34 @@ mod tests {
 
         let link = handle.link_get(&attr).unwrap();
 
-        let address = "127.0.0.2/24".parse().unwrap();
-        let addr = addr::Address {
-            ip: address,
-            ..Default::default()
-        };
+        // each RTM_NEWADDR reply is roughly 80 bytes, so this is well over 64 KiB
+        let count = 2000;
 
-        let proto = libc::RTM_NEWADDR;
-        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+        for i in 0..count {
+            let addr = addr::Address {
+                ip: format!("10.{}.{}.1/32", i / 256, i % 256).parse().unwrap(),
+                ..Default::default()
+            };
 
-        handle.addr_handle(&link, &addr, proto, flags).unwrap();
+            handle
+                .addr_handle(
+                    &link,
+                    &addr,
+                    libc::RTM_NEWADDR,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
 
-        let addrs = handle.addr_show(&link, libc::AF_UNSPEC).unwrap();
+        let addrs = handle.addr_show(&link, libc::AF_INET).unwrap();
 
-        assert_eq!(addrs.len(), 1);
-        assert_eq!(addrs[0].ip, address);
+        assert_eq!(
+            addrs
+                .iter()
+                .filter(|addr| addr.ip.addr().to_string().starts_with("10."))
+                .count(),
+            count
+        );
     }
 
     #[test]
@@ -751,4 +3711,63 @@ mod tests {
         let res = handle.route_get(&route.dst.unwrap().addr()).err();
         assert!(res.is_some());
     }
+
+    #[test]
+    fn test_route_get() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE).unwrap();
+        let mut attr = link::LinkAttrs::new();
+        attr.name = "foo".to_string();
+
+        handle
+            .link_new(
+                &Kind::Veth {
+                    attrs: attr.clone(),
+                    peer_name: "bar".to_string(),
+                    peer_hw_addr: None,
+                    peer_ns: None,
+                    peer_index: None,
+                },
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = handle.link_get(&attr).unwrap();
+        handle.link_setup(&link).unwrap();
+
+        let mut peer_attr = link::LinkAttrs::new();
+        peer_attr.name = "bar".to_string();
+
+        let peer = handle.link_get(&peer_attr).unwrap();
+        handle.link_setup(&peer).unwrap();
+
+        for (dst, index) in [
+            ("192.168.0.0/24", link.attrs().index),
+            ("10.10.0.0/16", peer.attrs().index),
+        ] {
+            let route = Route {
+                oif_index: index,
+                dst: Some(dst.parse().unwrap()),
+                scope: libc::RT_SCOPE_LINK,
+                ..Default::default()
+            };
+
+            handle
+                .route_handle(
+                    &route,
+                    libc::RTM_NEWROUTE,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
+
+        let dst = "10.10.1.1".parse().unwrap();
+        let routes = handle.route_get(&dst).unwrap();
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, peer.attrs().index);
+        assert!(routes[0].dst.unwrap().contains(&dst));
+
+        assert!(handle.route_get(&"172.16.0.1".parse().unwrap()).is_err());
+    }
 }
@@ -1,8 +1,18 @@
+// diagnostics only go through the `log` facade when the "log" feature is
+// enabled, callers like the CNI plugin use stdout for their own output
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    };
+}
+
 pub mod addr;
 pub mod consts;
 pub mod handle;
 pub mod link;
 pub mod message;
+pub mod neigh;
 pub mod netlink;
 pub mod request;
 pub mod route;
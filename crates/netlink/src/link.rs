@@ -1,11 +1,18 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    net::{IpAddr, Ipv4Addr},
+    os::fd::AsRawFd,
+    path::Path,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::{
     consts,
     message::{InfoMessage, NetlinkRouteAttr},
     request::NetlinkRequestData,
+    utils::{parse_bytes, parse_i32, parse_str, parse_u16, parse_u32, parse_u8, vec_to_addr},
 };
 
 pub enum Namespace {
@@ -13,6 +20,15 @@ pub enum Namespace {
     Fd(i32),
 }
 
+impl Namespace {
+    // the descriptor belongs to the returned file, which has to stay open for
+    // as long as the namespace is used
+    pub fn from_path(path: &Path) -> Result<(Self, File)> {
+        let file = File::open(path)?;
+        Ok((Self::Fd(file.as_raw_fd()), file))
+    }
+}
+
 pub enum Kind {
     Device(LinkAttrs),
     Dummy(LinkAttrs),
@@ -22,15 +38,205 @@ pub enum Kind {
         ageing_time: Option<u32>,
         multicast_snooping: Option<bool>,
         vlan_filtering: Option<bool>,
+        stp_state: Option<u32>,
+        priority: Option<u16>,
+        forward_delay: Option<u32>,
+        max_age: Option<u32>,
     },
     Veth {
         attrs: LinkAttrs,
         peer_name: String,
         peer_hw_addr: Option<Vec<u8>>,
         peer_ns: Option<Namespace>,
+        peer_index: Option<i32>,
+    },
+    MacVlan {
+        attrs: LinkAttrs,
+        mode: MacVlanMode,
+    },
+    Vxlan {
+        attrs: LinkAttrs,
+        vni: u32,
+        local: Option<IpAddr>,
+        group: Option<IpAddr>,
+        dst_port: Option<u16>,
+        learning: Option<bool>,
+    },
+    Bond {
+        attrs: LinkAttrs,
+        mode: BondMode,
+        miimon: Option<u32>,
+    },
+    Tuntap {
+        attrs: LinkAttrs,
+        mode: TuntapMode,
+        owner: Option<u32>,
+        group: Option<u32>,
+    },
+    Gre {
+        attrs: LinkAttrs,
+        local: Ipv4Addr,
+        remote: Ipv4Addr,
+        ttl: Option<u8>,
+        key: Option<u32>,
+    },
+    // keys and peers are configured over the generic netlink wireguard family,
+    // rtnetlink only creates and deletes the interface
+    Wireguard {
+        attrs: LinkAttrs,
     },
 }
 
+impl Kind {
+    // a bridge with every option left to the kernel default
+    pub fn bridge(attrs: LinkAttrs) -> Self {
+        Kind::Bridge {
+            attrs,
+            hello_time: None,
+            ageing_time: None,
+            multicast_snooping: None,
+            vlan_filtering: None,
+            stp_state: None,
+            priority: None,
+            forward_delay: None,
+            max_age: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuntapMode {
+    Tun,
+    Tap,
+}
+
+impl From<TuntapMode> for u8 {
+    fn from(mode: TuntapMode) -> Self {
+        match mode {
+            TuntapMode::Tun => libc::IFF_TUN as u8,
+            TuntapMode::Tap => libc::IFF_TAP as u8,
+        }
+    }
+}
+
+impl TryFrom<u8> for TuntapMode {
+    type Error = anyhow::Error;
+
+    fn try_from(mode: u8) -> Result<Self> {
+        match mode as i32 {
+            libc::IFF_TUN => Ok(TuntapMode::Tun),
+            libc::IFF_TAP => Ok(TuntapMode::Tap),
+            _ => bail!("unknown tuntap mode: {}", mode),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondMode {
+    BalanceRr,
+    ActiveBackup,
+    BalanceXor,
+    Broadcast,
+    Lacp,
+    BalanceTlb,
+    BalanceAlb,
+}
+
+impl From<BondMode> for u8 {
+    fn from(mode: BondMode) -> Self {
+        match mode {
+            BondMode::BalanceRr => consts::BOND_MODE_ROUNDROBIN,
+            BondMode::ActiveBackup => consts::BOND_MODE_ACTIVEBACKUP,
+            BondMode::BalanceXor => consts::BOND_MODE_XOR,
+            BondMode::Broadcast => consts::BOND_MODE_BROADCAST,
+            BondMode::Lacp => consts::BOND_MODE_8023AD,
+            BondMode::BalanceTlb => consts::BOND_MODE_TLB,
+            BondMode::BalanceAlb => consts::BOND_MODE_ALB,
+        }
+    }
+}
+
+impl TryFrom<u8> for BondMode {
+    type Error = anyhow::Error;
+
+    fn try_from(mode: u8) -> Result<Self> {
+        Ok(match mode {
+            consts::BOND_MODE_ROUNDROBIN => BondMode::BalanceRr,
+            consts::BOND_MODE_ACTIVEBACKUP => BondMode::ActiveBackup,
+            consts::BOND_MODE_XOR => BondMode::BalanceXor,
+            consts::BOND_MODE_BROADCAST => BondMode::Broadcast,
+            consts::BOND_MODE_8023AD => BondMode::Lacp,
+            consts::BOND_MODE_TLB => BondMode::BalanceTlb,
+            consts::BOND_MODE_ALB => BondMode::BalanceAlb,
+            _ => bail!("unknown bond mode: {}", mode),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacVlanMode {
+    Private,
+    Vepa,
+    Bridge,
+    Passthru,
+}
+
+impl From<MacVlanMode> for u32 {
+    fn from(mode: MacVlanMode) -> Self {
+        match mode {
+            MacVlanMode::Private => consts::MACVLAN_MODE_PRIVATE,
+            MacVlanMode::Vepa => consts::MACVLAN_MODE_VEPA,
+            MacVlanMode::Bridge => consts::MACVLAN_MODE_BRIDGE,
+            MacVlanMode::Passthru => consts::MACVLAN_MODE_PASSTHRU,
+        }
+    }
+}
+
+impl TryFrom<u32> for MacVlanMode {
+    type Error = anyhow::Error;
+
+    fn try_from(mode: u32) -> Result<Self> {
+        Ok(match mode {
+            consts::MACVLAN_MODE_PRIVATE => MacVlanMode::Private,
+            consts::MACVLAN_MODE_VEPA => MacVlanMode::Vepa,
+            consts::MACVLAN_MODE_BRIDGE => MacVlanMode::Bridge,
+            consts::MACVLAN_MODE_PASSTHRU => MacVlanMode::Passthru,
+            _ => bail!("unknown macvlan mode: {}", mode),
+        })
+    }
+}
+
+// RFC 2863 operational state, as reported in IFLA_OPERSTATE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    Unknown,
+    NotPresent,
+    Down,
+    LowerLayerDown,
+    Testing,
+    Dormant,
+    Up,
+}
+
+impl From<u8> for OperState {
+    fn from(state: u8) -> Self {
+        match state as i32 {
+            libc::IF_OPER_NOTPRESENT => OperState::NotPresent,
+            libc::IF_OPER_DOWN => OperState::Down,
+            libc::IF_OPER_LOWERLAYERDOWN => OperState::LowerLayerDown,
+            libc::IF_OPER_TESTING => OperState::Testing,
+            libc::IF_OPER_DORMANT => OperState::Dormant,
+            libc::IF_OPER_UP => OperState::Up,
+            _ => OperState::Unknown,
+        }
+    }
+}
+
+pub enum LinkEvent {
+    New(Box<dyn Link>),
+    Del(Box<dyn Link>),
+}
+
 pub trait Link {
     fn link_type(&self) -> String;
     fn attrs(&self) -> &LinkAttrs;
@@ -70,7 +276,7 @@ pub struct LinkAttrs {
     pub tx_queue_len: i32,
     pub alias: String,
     pub xdp: LinkXdp,
-    pub prot_info: String,
+    pub prot_info: Option<BrPortOptions>,
     pub oper_state: u8,
     pub phys_switch_id: i32,
     pub netns_id: i32,
@@ -89,6 +295,82 @@ impl LinkAttrs {
         Self::default()
     }
 
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_mtu(mut self, mtu: u32) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    pub fn with_hw_addr(mut self, mac: [u8; 6]) -> Self {
+        self.set_hw_addr(mac);
+        self
+    }
+
+    pub fn with_alias(mut self, alias: &str) -> Self {
+        self.set_alias(alias);
+        self
+    }
+
+    pub fn with_parent_index(mut self, index: i32) -> Self {
+        self.parent_index = index;
+        self
+    }
+
+    pub fn with_master_index(mut self, index: i32) -> Self {
+        self.master_index = index;
+        self
+    }
+
+    pub fn with_tx_queue_len(mut self, len: i32) -> Self {
+        self.tx_queue_len = len;
+        self
+    }
+
+    pub fn with_group(mut self, group: u32) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn hw_addr(&self) -> &[u8] {
+        &self.hw_addr
+    }
+
+    pub fn set_hw_addr(&mut self, mac: [u8; 6]) {
+        self.hw_addr = mac.to_vec();
+    }
+
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    pub fn set_alias(&mut self, alias: &str) {
+        self.alias = alias.to_string();
+    }
+
+    pub fn oper_state(&self) -> OperState {
+        OperState::from(self.oper_state)
+    }
+
+    // ports report the index of their bridge or bond, 0 when not enslaved
+    pub fn master_index(&self) -> Option<i32> {
+        match self.master_index {
+            0 => None,
+            index => Some(index),
+        }
+    }
+
+    pub fn mac_string(&self) -> String {
+        self.hw_addr
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
     fn from(if_info_msg: InfoMessage) -> Self {
         let mut attrs = Self::new();
         attrs.index = if_info_msg.index;
@@ -104,6 +386,12 @@ impl Link for Kind {
             Kind::Dummy(_) => "dummy".to_string(),
             Kind::Bridge { .. } => "bridge".to_string(),
             Kind::Veth { .. } => "veth".to_string(),
+            Kind::MacVlan { .. } => "macvlan".to_string(),
+            Kind::Vxlan { .. } => "vxlan".to_string(),
+            Kind::Bond { .. } => "bond".to_string(),
+            Kind::Tuntap { .. } => "tun".to_string(),
+            Kind::Gre { .. } => "gre".to_string(),
+            Kind::Wireguard { .. } => "wireguard".to_string(),
         }
     }
 
@@ -113,6 +401,12 @@ impl Link for Kind {
             Kind::Dummy(attrs) => attrs,
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
+            Kind::MacVlan { attrs, .. } => attrs,
+            Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
+            Kind::Tuntap { attrs, .. } => attrs,
+            Kind::Gre { attrs, .. } => attrs,
+            Kind::Wireguard { attrs } => attrs,
         }
     }
 
@@ -122,6 +416,12 @@ impl Link for Kind {
             Kind::Dummy(attrs) => attrs,
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
+            Kind::MacVlan { attrs, .. } => attrs,
+            Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
+            Kind::Tuntap { attrs, .. } => attrs,
+            Kind::Gre { attrs, .. } => attrs,
+            Kind::Wireguard { attrs } => attrs,
         }
     }
 
@@ -151,17 +451,17 @@ impl LinkXdp {
         for attr in rt_attrs {
             match attr.rt_attr.rta_type {
                 consts::IFLA_XDP_FD => {
-                    xdp.fd = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                    xdp.fd = parse_i32(&attr.value)?;
                 }
                 consts::IFLA_XDP_ATTACHED => {
-                    xdp.attache_mode = attr.value[0].try_into()?;
-                    xdp.attached = attr.value[0] != 0;
+                    xdp.attache_mode = parse_u8(&attr.value)?.into();
+                    xdp.attached = xdp.attache_mode != 0;
                 }
                 consts::IFLA_XDP_FLAGS => {
-                    xdp.flags = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                    xdp.flags = parse_u32(&attr.value)?;
                 }
                 consts::IFLA_XDP_PROG_ID => {
-                    xdp.prog_id = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                    xdp.prog_id = parse_u32(&attr.value)?;
                 }
                 _ => {}
             }
@@ -171,38 +471,96 @@ impl LinkXdp {
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BrPortOptions {
+    pub cost: Option<u32>,
+    pub priority: Option<u16>,
+    pub guard: Option<bool>,
+    pub hairpin: Option<bool>,
+    pub fast_leave: Option<bool>,
+    pub root_block: Option<bool>,
+    pub learning: Option<bool>,
+    pub unicast_flood: Option<bool>,
+}
+
+impl BrPortOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut opts = Self::new();
+
+        let rt_attrs = NetlinkRouteAttr::from(data)?;
+        for attr in rt_attrs {
+            match attr.rt_attr.rta_type {
+                consts::IFLA_BRPORT_COST => {
+                    opts.cost = Some(parse_u32(&attr.value)?);
+                }
+                consts::IFLA_BRPORT_PRIORITY => {
+                    opts.priority = Some(parse_u16(&attr.value)?);
+                }
+                consts::IFLA_BRPORT_GUARD => {
+                    opts.guard = Some(parse_u8(&attr.value)? == 1);
+                }
+                consts::IFLA_BRPORT_MODE => {
+                    opts.hairpin = Some(parse_u8(&attr.value)? == 1);
+                }
+                consts::IFLA_BRPORT_FAST_LEAVE => {
+                    opts.fast_leave = Some(parse_u8(&attr.value)? == 1);
+                }
+                consts::IFLA_BRPORT_PROTECT => {
+                    opts.root_block = Some(parse_u8(&attr.value)? == 1);
+                }
+                consts::IFLA_BRPORT_LEARNING => {
+                    opts.learning = Some(parse_u8(&attr.value)? == 1);
+                }
+                consts::IFLA_BRPORT_UNICAST_FLOOD => {
+                    opts.unicast_flood = Some(parse_u8(&attr.value)? == 1);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(opts)
+    }
+}
+
 pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
+    Ok(Box::new(link_kind_deserialize(buf)?))
+}
+
+pub fn link_kind_deserialize(buf: &[u8]) -> Result<Kind> {
     let if_info_msg = InfoMessage::deserialize(buf)?;
-    let rt_attrs = NetlinkRouteAttr::from(&buf[if_info_msg.len()..])?;
 
     let mut base = LinkAttrs::from(if_info_msg);
     let mut data = HashMap::new();
 
-    for attr in rt_attrs {
-        match attr.rt_attr.rta_type {
-            libc::IFLA_LINKINFO => {
-                data = extract_link_info(&mut base, NetlinkRouteAttr::from(&attr.value)?)?
-            }
+    for attr in NetlinkRouteAttr::iter(&buf[if_info_msg.len()..]) {
+        let (rt_attr, value) = attr?;
+
+        match rt_attr.rta_type {
+            libc::IFLA_LINKINFO => data = extract_link_info(&mut base, value)?,
             libc::IFLA_ADDRESS => {
-                base.hw_addr = attr.value;
+                base.hw_addr = value.to_vec();
             }
             libc::IFLA_IFNAME => {
-                base.name = String::from_utf8(attr.value[..attr.value.len() - 1].to_vec())?;
+                base.name = parse_str(value)?.to_string();
             }
             libc::IFLA_MTU => {
-                base.mtu = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.mtu = parse_u32(value)?;
             }
             libc::IFLA_LINK => {
-                base.parent_index = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.parent_index = parse_i32(value)?;
             }
             libc::IFLA_MASTER => {
-                base.master_index = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.master_index = parse_i32(value)?;
             }
             libc::IFLA_TXQLEN => {
-                base.tx_queue_len = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.tx_queue_len = parse_i32(value)?;
             }
             libc::IFLA_IFALIAS => {
-                base.alias = String::from_utf8(attr.value[..attr.value.len() - 1].to_vec())?;
+                base.alias = parse_str(value)?.to_string();
             }
             libc::IFLA_STATS => {
                 // TODO
@@ -211,95 +569,270 @@ pub fn link_deserialize(buf: &[u8]) -> Result<Box<dyn Link>> {
                 // TODO
             }
             libc::IFLA_XDP => {
-                base.xdp = LinkXdp::parse(&attr.value)?;
+                base.xdp = LinkXdp::parse(value)?;
             }
-            libc::IFLA_PROTINFO | consts::NLA_F_NESTED => {
-                // TODO
+            t if t & !consts::NLA_F_NESTED == libc::IFLA_PROTINFO => {
+                base.prot_info = Some(BrPortOptions::parse(value)?);
             }
             libc::IFLA_OPERSTATE => {
-                base.oper_state = attr.value[0];
+                base.oper_state = parse_u8(value)?;
             }
             libc::IFLA_PHYS_SWITCH_ID => {
-                base.phys_switch_id = i32::from_be_bytes(attr.value[..4].try_into()?);
+                base.phys_switch_id = i32::from_be_bytes(parse_bytes(value)?);
             }
             libc::IFLA_LINK_NETNSID => {
-                base.netns_id = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.netns_id = parse_i32(value)?;
             }
             libc::IFLA_GSO_MAX_SIZE => {
-                base.gso_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.gso_max_size = parse_u32(value)?;
             }
             libc::IFLA_GSO_MAX_SEGS => {
-                base.gso_max_segs = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.gso_max_segs = parse_u32(value)?;
             }
             consts::IFLA_GRO_MAX_SIZE => {
-                base.gro_max_size = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.gro_max_size = parse_u32(value)?;
             }
             libc::IFLA_VFINFO_LIST => {
                 // TODO
             }
             libc::IFLA_NUM_TX_QUEUES => {
-                base.num_tx_queues = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.num_tx_queues = parse_i32(value)?;
             }
             libc::IFLA_NUM_RX_QUEUES => {
-                base.num_rx_queues = i32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.num_rx_queues = parse_i32(value)?;
             }
             libc::IFLA_GROUP => {
-                base.group = u32::from_ne_bytes(attr.value[..4].try_into()?);
+                base.group = parse_u32(value)?;
             }
             _ => {}
         }
     }
 
     Ok(match &base.link_type[..] {
-        "device" => Box::new(Kind::Device(base)),
-        "dummy" => Box::new(Kind::Dummy(base)),
-        "bridge" => Box::new(Kind::Bridge {
+        "device" => Kind::Device(base),
+        "dummy" => Kind::Dummy(base),
+        "bridge" => Kind::Bridge {
             attrs: base,
             hello_time: data
                 .get(&consts::IFLA_BR_HELLO_TIME)
-                .map(|v| u32::from_ne_bytes(v[..4].try_into().unwrap_or([0; 4]))),
+                .map(|v| parse_u32(v))
+                .transpose()?,
             ageing_time: data
                 .get(&consts::IFLA_BR_AGEING_TIME)
-                .map(|v| u32::from_ne_bytes(v[..4].try_into().unwrap_or([0; 4]))),
-            multicast_snooping: data.get(&consts::IFLA_BR_MCAST_SNOOPING).map(|v| v[0] == 1),
-            vlan_filtering: data.get(&consts::IFLA_BR_VLAN_FILTERING).map(|v| v[0] == 1),
-        }),
-        "veth" => Box::new(Kind::Veth {
+                .map(|v| parse_u32(v))
+                .transpose()?,
+            multicast_snooping: data
+                .get(&consts::IFLA_BR_MCAST_SNOOPING)
+                .map(|v| parse_u8(v).map(|v| v == 1))
+                .transpose()?,
+            vlan_filtering: data
+                .get(&consts::IFLA_BR_VLAN_FILTERING)
+                .map(|v| parse_u8(v).map(|v| v == 1))
+                .transpose()?,
+            stp_state: data
+                .get(&consts::IFLA_BR_STP_STATE)
+                .map(|v| parse_u32(v))
+                .transpose()?,
+            priority: data
+                .get(&consts::IFLA_BR_PRIORITY)
+                .map(|v| parse_u16(v))
+                .transpose()?,
+            forward_delay: data
+                .get(&consts::IFLA_BR_FORWARD_DELAY)
+                .map(|v| parse_u32(v))
+                .transpose()?,
+            max_age: data
+                .get(&consts::IFLA_BR_MAX_AGE)
+                .map(|v| parse_u32(v))
+                .transpose()?,
+        },
+        "veth" => {
+            let mut peer_name = String::new();
+            let mut peer_hw_addr = None;
+            // the kernel reports the peer as IFLA_LINK unless the peer info is nested
+            let mut peer_index = Some(base.parent_index).filter(|index| *index != 0);
+
+            if let Some(peer) = data.get(&consts::VETH_INFO_PEER) {
+                if peer.len() >= consts::IF_INFO_MSG_SIZE {
+                    let peer_msg = InfoMessage::deserialize(peer)?;
+
+                    if peer_msg.index != 0 {
+                        peer_index = Some(peer_msg.index);
+                    }
+
+                    for attr in NetlinkRouteAttr::iter(&peer[peer_msg.len()..]) {
+                        let (rt_attr, value) = attr?;
+
+                        match rt_attr.rta_type {
+                            libc::IFLA_IFNAME => {
+                                peer_name = parse_str(value)?.to_string();
+                            }
+                            libc::IFLA_ADDRESS => {
+                                peer_hw_addr = Some(value.to_vec());
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            Kind::Veth {
+                attrs: base,
+                peer_name,
+                peer_hw_addr,
+                peer_ns: None,
+                peer_index,
+            }
+        }
+        "macvlan" => {
+            let mode = data
+                .get(&consts::IFLA_MACVLAN_MODE)
+                .map(|v| parse_u32(v))
+                .transpose()?
+                .unwrap_or(consts::MACVLAN_MODE_VEPA);
+
+            Kind::MacVlan {
+                attrs: base,
+                mode: MacVlanMode::try_from(mode)?,
+            }
+        }
+        "vxlan" => Kind::Vxlan {
             attrs: base,
-            peer_name: Default::default(),
-            peer_hw_addr: None,
-            peer_ns: None,
-        }),
-        _ => Box::new(Kind::Device(base)),
+            vni: data
+                .get(&consts::IFLA_VXLAN_ID)
+                .map(|v| parse_u32(v))
+                .transpose()?
+                .unwrap_or_default(),
+            local: data
+                .get(&consts::IFLA_VXLAN_LOCAL)
+                .or_else(|| data.get(&consts::IFLA_VXLAN_LOCAL6))
+                .map(|v| vec_to_addr(v.to_vec()))
+                .transpose()?,
+            group: data
+                .get(&consts::IFLA_VXLAN_GROUP)
+                .or_else(|| data.get(&consts::IFLA_VXLAN_GROUP6))
+                .map(|v| vec_to_addr(v.to_vec()))
+                .transpose()?,
+            dst_port: data
+                .get(&consts::IFLA_VXLAN_PORT)
+                .map(|v| parse_bytes(v).map(u16::from_be_bytes))
+                .transpose()?,
+            learning: data
+                .get(&consts::IFLA_VXLAN_LEARNING)
+                .map(|v| parse_u8(v).map(|v| v == 1))
+                .transpose()?,
+        },
+        "bond" => {
+            let mode = data
+                .get(&consts::IFLA_BOND_MODE)
+                .map(|v| parse_u8(v))
+                .transpose()?
+                .unwrap_or(consts::BOND_MODE_ROUNDROBIN);
+
+            Kind::Bond {
+                attrs: base,
+                mode: BondMode::try_from(mode)?,
+                miimon: data
+                    .get(&consts::IFLA_BOND_MIIMON)
+                    .map(|v| parse_u32(v))
+                    .transpose()?,
+            }
+        }
+        "tun" => {
+            let mode = data
+                .get(&consts::IFLA_TUN_TYPE)
+                .map(|v| parse_u8(v))
+                .transpose()?
+                .unwrap_or(libc::IFF_TUN as u8);
+
+            Kind::Tuntap {
+                attrs: base,
+                mode: TuntapMode::try_from(mode)?,
+                // the kernel reports -1 for owner and group when they are not set
+                owner: data
+                    .get(&consts::IFLA_TUN_OWNER)
+                    .map(|v| parse_u32(v))
+                    .transpose()?
+                    .filter(|id| *id != u32::MAX),
+                group: data
+                    .get(&consts::IFLA_TUN_GROUP)
+                    .map(|v| parse_u32(v))
+                    .transpose()?
+                    .filter(|id| *id != u32::MAX),
+            }
+        }
+        "gre" => {
+            let addr = |rta_type| -> Result<Ipv4Addr> {
+                Ok(data
+                    .get(&rta_type)
+                    .map(|v| parse_bytes(v).map(Ipv4Addr::from))
+                    .transpose()?
+                    .unwrap_or(Ipv4Addr::UNSPECIFIED))
+            };
+
+            // flags and keys are in network byte order, the key is only
+            // meaningful when GRE_KEY is set in the flags
+            let iflags = data
+                .get(&consts::IFLA_GRE_IFLAGS)
+                .map(|v| parse_bytes(v).map(u16::from_be_bytes))
+                .transpose()?
+                .unwrap_or_default();
+
+            let key = match iflags & consts::GRE_KEY {
+                0 => None,
+                _ => data
+                    .get(&consts::IFLA_GRE_IKEY)
+                    .map(|v| parse_bytes(v).map(u32::from_be_bytes))
+                    .transpose()?,
+            };
+
+            Kind::Gre {
+                attrs: base,
+                local: addr(consts::IFLA_GRE_LOCAL)?,
+                remote: addr(consts::IFLA_GRE_REMOTE)?,
+                ttl: data
+                    .get(&consts::IFLA_GRE_TTL)
+                    .map(|v| parse_u8(v))
+                    .transpose()?,
+                key,
+            }
+        }
+        "wireguard" => Kind::Wireguard { attrs: base },
+        _ => Kind::Device(base),
     })
 }
 
-fn extract_link_info(
-    base: &mut LinkAttrs,
-    infos: Vec<NetlinkRouteAttr>,
-) -> Result<HashMap<u16, Vec<u8>>> {
+fn extract_link_info(base: &mut LinkAttrs, infos: &[u8]) -> Result<HashMap<u16, Vec<u8>>> {
     let mut data = HashMap::new();
+    let mut slave_kind = "";
+    let mut slave_data: &[u8] = &[];
+
+    for info in NetlinkRouteAttr::iter(infos) {
+        let (rt_attr, value) = info?;
 
-    for info in infos {
-        match info.rt_attr.rta_type {
+        match rt_attr.rta_type {
             libc::IFLA_INFO_KIND => {
-                base.link_type = String::from_utf8(info.value[..info.value.len() - 1].to_vec())?;
+                base.link_type = parse_str(value)?.to_string();
             }
             libc::IFLA_INFO_DATA => {
-                data = NetlinkRouteAttr::map(&info.value)?;
+                data = NetlinkRouteAttr::map(value)?;
             }
             libc::IFLA_INFO_SLAVE_KIND => {
-                // TODO
+                slave_kind = parse_str(value)?;
             }
             libc::IFLA_INFO_SLAVE_DATA => {
-                // TODO
+                slave_data = value;
             }
             _ => {
-                println!("-> Unknown attribute: {}", info.rt_attr.rta_type);
+                debug!("unknown link info attribute: {}", rt_attr.rta_type);
             }
         }
     }
 
+    if slave_kind == "bridge" {
+        base.prot_info = Some(BrPortOptions::parse(slave_data)?);
+    }
+
     Ok(data)
 }
 
@@ -427,6 +960,84 @@ mod tests {
         0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00,
     ];
 
+    #[rustfmt::skip]
+    static VETH_MSG: [u8; 100] = [
+        0x00, // interface family
+        0x00, // reserved
+        0x01, 0x00, // link layer type 1 = ether
+        0x05, 0x00, 0x00, 0x00, // interface index = 5
+        0x03, 0x10, 0x00, 0x00, // device flags: UP, BROADCAST, MULTICAST
+        0x00, 0x00, 0x00, 0x00, // reserved 2 (aka device change flag)
+
+        // nlas
+        0x0a, 0x00, 0x03, 0x00, 0x76, 0x65, 0x74, 0x68, 0x30, 0x00, // device name L=10,T=3,V=veth0
+        0x00, 0x00, // padding
+        0x08, 0x00, 0x04, 0x00, 0xdc, 0x05, 0x00, 0x00, // MTU L=8,T=4,V=1500
+        0x40, 0x00, 0x12, 0x00, // link info L=64,T=18
+        0x09, 0x00, 0x01, 0x00, 0x76, 0x65, 0x74, 0x68, 0x00, // kind L=9,T=1,V=veth
+        0x00, 0x00, 0x00, // padding
+        0x30, 0x00, 0x02, 0x00, // info data L=48,T=2
+        0x2c, 0x00, 0x01, 0x00, // veth peer L=44,T=1
+        0x00, 0x00, 0x01, 0x00, // peer family, reserved, link layer type
+        0x06, 0x00, 0x00, 0x00, // peer interface index = 6
+        0x02, 0x10, 0x00, 0x00, // peer device flags: BROADCAST, MULTICAST
+        0x00, 0x00, 0x00, 0x00, // peer change flag
+        0x0a, 0x00, 0x03, 0x00, 0x76, 0x65, 0x74, 0x68, 0x31, 0x00, // peer name L=10,T=3,V=veth1
+        0x00, 0x00, // padding
+        0x0a, 0x00, 0x01, 0x00, 0x02, 0x42, 0xac, 0x11, 0x00, 0x03, // peer address L=10,T=1
+        0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn test_link_attrs_with() {
+        let attrs = LinkAttrs::new()
+            .with_name("foo")
+            .with_mtu(1400)
+            .with_hw_addr([0x02, 0x42, 0x3b, 0x14, 0xa7, 0x98])
+            .with_alias("bar")
+            .with_parent_index(2)
+            .with_master_index(3)
+            .with_tx_queue_len(500)
+            .with_group(7);
+
+        assert_eq!(attrs.name, "foo");
+        assert_eq!(attrs.mtu, 1400);
+        assert_eq!(attrs.mac_string(), "02:42:3b:14:a7:98");
+        assert_eq!(attrs.alias(), "bar");
+        assert_eq!(attrs.parent_index, 2);
+        assert_eq!(attrs.master_index, 3);
+        assert_eq!(attrs.master_index(), Some(3));
+        assert_eq!(LinkAttrs::new().master_index(), None);
+        assert_eq!(attrs.tx_queue_len, 500);
+        assert_eq!(attrs.group, 7);
+        assert_eq!(attrs.index, 0);
+    }
+
+    #[test]
+    fn test_link_deserialize_veth() {
+        let link = link_deserialize(&VETH_MSG).unwrap();
+        assert_eq!(link.link_type(), "veth");
+        assert_eq!(link.attrs().index, 5);
+        assert_eq!(link.attrs().name, "veth0");
+
+        match link.kind() {
+            Kind::Veth {
+                peer_name,
+                peer_hw_addr,
+                peer_index,
+                ..
+            } => {
+                assert_eq!(peer_index.unwrap(), 6);
+                assert_eq!(peer_name, "veth1");
+                assert_eq!(
+                    peer_hw_addr.as_ref().unwrap(),
+                    &[0x02, 0x42, 0xac, 0x11, 0x00, 0x03]
+                );
+            }
+            _ => panic!("Expected veth link"),
+        }
+    }
+
     #[test]
     fn test_link_deserialize() {
         let link = link_deserialize(&NETLINK_MSG).unwrap();
@@ -437,6 +1048,8 @@ mod tests {
         assert_eq!(attrs.name, "docker0");
         assert_eq!(attrs.mtu, 1500);
         assert_eq!(attrs.raw_flags, 0x1003);
+        assert_eq!(attrs.hw_addr(), &[0x02, 0x42, 0x3b, 0x14, 0xa7, 0x98]);
+        assert_eq!(attrs.mac_string(), "02:42:3b:14:a7:98");
 
         match link.kind() {
             Kind::Bridge {
@@ -445,6 +1058,7 @@ mod tests {
                 ageing_time,
                 multicast_snooping,
                 vlan_filtering,
+                ..
             } => {
                 assert_eq!(hello_time.unwrap(), 200);
                 assert_eq!(ageing_time.unwrap(), 30000);
@@ -454,4 +1068,142 @@ mod tests {
             _ => panic!("Expected bridge link"),
         }
     }
+
+    #[test]
+    fn test_link_deserialize_short_attr() {
+        let msg = [
+            0x00, 0x00, 0x01, 0x00, // family, reserved, link layer type
+            0x05, 0x00, 0x00, 0x00, // interface index = 5
+            0x00, 0x00, 0x00, 0x00, // device flags
+            0x00, 0x00, 0x00, 0x00, // change flag
+            0x06, 0x00, 0x04, 0x00, 0xdc, 0x05, // mtu L=6,T=4, only 2 bytes of value
+            0x00, 0x00, // padding
+        ];
+
+        let err = link_deserialize(&msg).err().unwrap();
+        assert!(err.to_string().contains("expected 4 bytes, got 2"));
+    }
+
+    #[test]
+    fn test_link_deserialize_quiet() {
+        // the test harness swallows println!, so the deserialization runs in a
+        // child process with capturing disabled and its stdout is inspected
+        if std::env::var("NETLINK_QUIET_CHILD").is_ok() {
+            let msg = [
+                0x00, 0x00, 0x01, 0x00, // family, reserved, link layer type
+                0x05, 0x00, 0x00, 0x00, // interface index = 5
+                0x00, 0x00, 0x00, 0x00, // device flags
+                0x00, 0x00, 0x00, 0x00, // change flag
+                0x14, 0x00, 0x12, 0x00, // link info L=20,T=18
+                0x0a, 0x00, 0x01, 0x00, 0x64, 0x75, 0x6d, 0x6d, 0x79,
+                0x00, // kind L=10,T=1,V=dummy
+                0x00, 0x00, // padding
+                0x04, 0x00, 0x03, 0x00, // xstats L=4,T=3, not handled
+            ];
+
+            print!("<start>");
+            link_deserialize(&msg).unwrap();
+            print!("<end>");
+            return;
+        }
+
+        let out = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "link::tests::test_link_deserialize_quiet"])
+            .args(["--nocapture", "--test-threads=1"])
+            .env("NETLINK_QUIET_CHILD", "1")
+            .output()
+            .unwrap();
+
+        assert!(out.status.success());
+        assert!(String::from_utf8_lossy(&out.stdout).contains("<start><end>"));
+    }
+
+    #[rustfmt::skip]
+    static LO_MSG: [u8; 32] = [
+        0x00, // interface family
+        0x00, // reserved
+        0x04, 0x03, // link layer type 772 = loopback
+        0x01, 0x00, 0x00, 0x00, // interface index = 1
+        0x49, 0x00, 0x00, 0x00, // device flags: UP, LOOPBACK, RUNNING, LOWERUP
+        0x00, 0x00, 0x00, 0x00, // reserved 2 (aka device change flag)
+
+        // nlas
+        0x07, 0x00, 0x03, 0x00, 0x6c, 0x6f, 0x00, // device name L=7,T=3,V=lo
+        0x00, // padding
+        0x05, 0x00, 0x10, 0x00, 0x00, // OperState L=5,T=16,V=0 (unknown)
+        0x00, 0x00, 0x00, // padding
+    ];
+
+    #[test]
+    fn test_link_oper_state() {
+        let link = link_deserialize(&LO_MSG).unwrap();
+        assert_eq!(link.attrs().name, "lo");
+        assert_eq!(link.attrs().oper_state(), OperState::Unknown);
+
+        assert_eq!(OperState::from(libc::IF_OPER_UP as u8), OperState::Up);
+        assert_eq!(OperState::from(libc::IF_OPER_DOWN as u8), OperState::Down);
+    }
+
+    #[rustfmt::skip]
+    static GRE_MSG: [u8; 92] = [
+        0x00, 0x00, 0x30, 0x03, // family, reserved, link layer type 816 = ipgre
+        0x07, 0x00, 0x00, 0x00, // interface index = 7
+        0x80, 0x00, 0x00, 0x00, // device flags: NOARP
+        0x00, 0x00, 0x00, 0x00, // change flag
+        0x09, 0x00, 0x03, 0x00, 0x67, 0x72, 0x65, 0x31, 0x00, // device name L=9,T=3,V=gre1
+        0x00, 0x00, 0x00, // padding
+        0x40, 0x00, 0x12, 0x00, // link info L=64,T=18
+        0x08, 0x00, 0x01, 0x00, 0x67, 0x72, 0x65, 0x00, // kind L=8,T=1,V=gre
+        0x34, 0x00, 0x02, 0x00, // info data L=52,T=2
+        0x06, 0x00, 0x02, 0x00, 0x20, 0x00, 0x00, 0x00, // iflags L=6,T=2,V=GRE_KEY (be16), padding
+        0x08, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x2a, // ikey L=8,T=4,V=42 (be32)
+        0x08, 0x00, 0x06, 0x00, 0x7f, 0x00, 0x00, 0x01, // local L=8,T=6,V=127.0.0.1
+        0x08, 0x00, 0x07, 0x00, 0x7f, 0x00, 0x00, 0x02, // remote L=8,T=7,V=127.0.0.2
+        0x05, 0x00, 0x08, 0x00, 0x40, 0x00, 0x00, 0x00, // ttl L=5,T=8,V=64, padding
+        0x06, 0x00, 0x03, 0x00, 0x20, 0x00, 0x00, 0x00, // oflags L=6,T=3,V=GRE_KEY (be16), padding
+    ];
+
+    #[test]
+    fn test_link_deserialize_gre() {
+        let link = link_deserialize(&GRE_MSG).unwrap();
+        assert_eq!(link.link_type(), "gre");
+        assert_eq!(link.attrs().name, "gre1");
+
+        match link.kind() {
+            Kind::Gre {
+                local,
+                remote,
+                ttl,
+                key,
+                ..
+            } => {
+                assert_eq!(*local, Ipv4Addr::new(127, 0, 0, 1));
+                assert_eq!(*remote, Ipv4Addr::new(127, 0, 0, 2));
+                assert_eq!(ttl.unwrap(), 64);
+                assert_eq!(key.unwrap(), 42);
+            }
+            _ => panic!("Expected gre link"),
+        }
+    }
+
+    #[test]
+    fn test_link_deserialize_wireguard() {
+        #[rustfmt::skip]
+        let msg = [
+            0x00, 0x00, 0xfe, 0xff, // family, reserved, link layer type 65534 = none
+            0x08, 0x00, 0x00, 0x00, // interface index = 8
+            0x80, 0x00, 0x00, 0x00, // device flags: NOARP
+            0x00, 0x00, 0x00, 0x00, // change flag
+            0x08, 0x00, 0x03, 0x00, 0x77, 0x67, 0x30, 0x00, // device name L=8,T=3,V=wg0
+            0x14, 0x00, 0x12, 0x00, // link info L=20,T=18
+            0x0e, 0x00, 0x01, 0x00, 0x77, 0x69, 0x72, 0x65, 0x67, 0x75, 0x61, 0x72, 0x64,
+            0x00, // kind L=14,T=1,V=wireguard
+            0x00, 0x00, // padding
+        ];
+
+        let link = link_deserialize(&msg).unwrap();
+        assert_eq!(link.link_type(), "wireguard");
+        assert_eq!(link.attrs().name, "wg0");
+        assert!(matches!(link.kind(), Kind::Wireguard { .. }));
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::Serialize;
 
 use crate::{consts, request::NetlinkRequestData, utils::align_of};
@@ -15,9 +15,22 @@ impl NetlinkMessage {
         let mut msgs = Vec::new();
 
         while buf.len() >= consts::NLMSG_HDRLEN {
-            let header = unsafe { *(buf.as_ptr() as *const NetlinkMessageHeader) };
-            let len = align_of(header.nlmsg_len as usize, consts::NLMSG_ALIGNTO);
-            let data = buf[consts::NLMSG_HDRLEN..header.nlmsg_len as usize].to_vec();
+            let header = NetlinkMessageHeader::deserialize(buf);
+            let msg_len = header.nlmsg_len as usize;
+
+            if msg_len < consts::NLMSG_HDRLEN || msg_len > buf.len() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "invalid netlink message length {}, remaining buffer {}",
+                        msg_len,
+                        buf.len()
+                    ),
+                ));
+            }
+
+            let len = align_of(msg_len, consts::NLMSG_ALIGNTO).min(buf.len());
+            let data = buf[consts::NLMSG_HDRLEN..msg_len].to_vec();
 
             msgs.push(Self { header, data });
             buf = &buf[len..];
@@ -38,6 +51,17 @@ pub struct NetlinkMessageHeader {
 }
 
 impl NetlinkMessageHeader {
+    // callers must make sure that buf holds at least NLMSG_HDRLEN bytes
+    fn deserialize(buf: &[u8]) -> Self {
+        Self {
+            nlmsg_len: u32::from_ne_bytes(buf[0..4].try_into().unwrap()),
+            nlmsg_type: u16::from_ne_bytes(buf[4..6].try_into().unwrap()),
+            nlmsg_flags: u16::from_ne_bytes(buf[6..8].try_into().unwrap()),
+            nlmsg_seq: u32::from_ne_bytes(buf[8..12].try_into().unwrap()),
+            nlmsg_pid: u32::from_ne_bytes(buf[12..16].try_into().unwrap()),
+        }
+    }
+
     pub fn new(proto: u16, flags: i32) -> Self {
         Self {
             nlmsg_len: std::mem::size_of::<Self>() as u32,
@@ -70,6 +94,9 @@ impl NetlinkRequestData for NetlinkRouteAttr {
         buf.extend_from_slice(&self.rt_attr.rta_type.to_ne_bytes());
         buf.extend_from_slice(&self.value);
 
+        // the attribute length excludes the trailing padding of its own value
+        let mut len = buf.len();
+
         let align_to = align_of(buf.len(), consts::RTA_ALIGNTO);
         if buf.len() < align_to {
             buf.resize(align_to, 0);
@@ -79,9 +106,9 @@ impl NetlinkRequestData for NetlinkRouteAttr {
             for child in children {
                 buf.extend_from_slice(&child.serialize()?);
             }
+            len = buf.len();
         }
 
-        let len = buf.len();
         buf[..2].copy_from_slice(&(len as u16).to_ne_bytes());
 
         Ok(buf)
@@ -100,38 +127,27 @@ impl NetlinkRouteAttr {
         }
     }
 
-    pub fn map(mut buf: &[u8]) -> Result<HashMap<u16, Vec<u8>>> {
-        let mut attrs = HashMap::new();
-
-        while buf.len() >= consts::RT_ATTR_SIZE {
-            let rt_attr = unsafe { *(buf.as_ptr() as *const RtAttr) };
-            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO);
-            let value = buf[consts::RT_ATTR_SIZE..rt_attr.rta_len as usize].to_vec();
-
-            attrs.insert(rt_attr.rta_type, value);
-            buf = &buf[len..];
-        }
-
-        Ok(attrs)
+    // walks the attributes in buf without copying their values
+    pub fn iter(buf: &[u8]) -> RtAttrIter<'_> {
+        RtAttrIter { buf }
     }
 
-    pub fn from(mut buf: &[u8]) -> Result<Vec<Self>> {
-        let mut attrs = Vec::new();
-
-        while buf.len() >= consts::RT_ATTR_SIZE {
-            let rt_attr = unsafe { *(buf.as_ptr() as *const RtAttr) };
-            let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO);
-            let value = buf[consts::RT_ATTR_SIZE..rt_attr.rta_len as usize].to_vec();
-
-            attrs.push(Self {
-                rt_attr,
-                value,
-                children: None,
-            });
-            buf = &buf[len..];
-        }
+    pub fn map(buf: &[u8]) -> Result<HashMap<u16, Vec<u8>>> {
+        Self::iter(buf)
+            .map(|attr| attr.map(|(rt_attr, value)| (rt_attr.rta_type, value.to_vec())))
+            .collect()
+    }
 
-        Ok(attrs)
+    pub fn from(buf: &[u8]) -> Result<Vec<Self>> {
+        Self::iter(buf)
+            .map(|attr| {
+                attr.map(|(rt_attr, value)| Self {
+                    rt_attr,
+                    value: value.to_vec(),
+                    children: None,
+                })
+            })
+            .collect()
     }
 
     pub fn add_child(&mut self, rta_type: u16, value: Vec<u8>) {
@@ -144,7 +160,7 @@ impl NetlinkRouteAttr {
         }
     }
 
-    pub fn add_child_from_attr(&mut self, attr: Box<(impl NetlinkRequestData + 'static)>) {
+    pub fn add_child_from_attr(&mut self, attr: Box<impl NetlinkRequestData + 'static>) {
         self.rt_attr.rta_len += attr.len() as u16;
 
         match &mut self.children {
@@ -154,6 +170,35 @@ impl NetlinkRouteAttr {
     }
 }
 
+pub struct RtAttrIter<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for RtAttrIter<'a> {
+    type Item = Result<(RtAttr, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.len() < consts::RT_ATTR_SIZE {
+            return None;
+        }
+
+        let rt_attr = match RtAttr::deserialize(self.buf) {
+            Ok(rt_attr) => rt_attr,
+            Err(e) => {
+                // nothing after a malformed attribute can be trusted
+                self.buf = &[];
+                return Some(Err(e));
+            }
+        };
+
+        let len = align_of(rt_attr.rta_len as usize, consts::RTA_ALIGNTO).min(self.buf.len());
+        let value = &self.buf[consts::RT_ATTR_SIZE..rt_attr.rta_len as usize];
+        self.buf = &self.buf[len..];
+
+        Some(Ok((rt_attr, value)))
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 pub struct RtAttr {
@@ -161,8 +206,29 @@ pub struct RtAttr {
     pub rta_type: u16,
 }
 
+impl RtAttr {
+    // callers must make sure that buf holds at least RT_ATTR_SIZE bytes
+    fn deserialize(buf: &[u8]) -> Result<Self> {
+        let rt_attr = Self {
+            rta_len: u16::from_ne_bytes(buf[0..2].try_into()?),
+            rta_type: u16::from_ne_bytes(buf[2..4].try_into()?),
+        };
+
+        let len = rt_attr.rta_len as usize;
+        if len < consts::RT_ATTR_SIZE || len > buf.len() {
+            bail!(
+                "invalid route attribute length {}, remaining buffer {}",
+                len,
+                buf.len()
+            );
+        }
+
+        Ok(rt_attr)
+    }
+}
+
 #[repr(C)]
-#[derive(Clone, Copy, Default, Debug, Serialize)]
+#[derive(Clone, Copy, Default, Debug)]
 pub struct InfoMessage {
     pub family: u8,
     pub _pad: u8,
@@ -182,7 +248,14 @@ impl NetlinkRequestData for InfoMessage {
     }
 
     fn serialize(&self) -> Result<Vec<u8>> {
-        bincode::serialize(self).map_err(|e| e.into())
+        let mut buf = Vec::with_capacity(consts::IF_INFO_MSG_SIZE);
+        buf.push(self.family);
+        buf.push(self._pad);
+        buf.extend_from_slice(&self.ifi_type.to_ne_bytes());
+        buf.extend_from_slice(&self.index.to_ne_bytes());
+        buf.extend_from_slice(&self.flags.to_ne_bytes());
+        buf.extend_from_slice(&self.change.to_ne_bytes());
+        Ok(buf)
     }
 }
 
@@ -195,12 +268,21 @@ impl InfoMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::IF_INFO_MSG_SIZE].as_ptr() as *const Self) })
+        check_len(buf, consts::IF_INFO_MSG_SIZE, "InfoMessage")?;
+
+        Ok(Self {
+            family: buf[0],
+            ifi_type: u16::from_ne_bytes(buf[2..4].try_into()?),
+            index: i32::from_ne_bytes(buf[4..8].try_into()?),
+            flags: u32::from_ne_bytes(buf[8..12].try_into()?),
+            change: u32::from_ne_bytes(buf[12..16].try_into()?),
+            ..Default::default()
+        })
     }
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Default, Debug, Serialize)]
+#[derive(Clone, Copy, Default, Debug)]
 pub struct AddressMessage {
     pub family: u8,
     pub prefix_len: u8,
@@ -219,7 +301,13 @@ impl NetlinkRequestData for AddressMessage {
     }
 
     fn serialize(&self) -> Result<Vec<u8>> {
-        bincode::serialize(self).map_err(|e| e.into())
+        let mut buf = Vec::with_capacity(consts::IF_ADDR_MSG_SIZE);
+        buf.push(self.family);
+        buf.push(self.prefix_len);
+        buf.push(self.flags);
+        buf.push(self.scope);
+        buf.extend_from_slice(&self.index.to_ne_bytes());
+        Ok(buf)
     }
 }
 
@@ -232,7 +320,15 @@ impl AddressMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::IF_ADDR_MSG_SIZE].as_ptr() as *const Self) })
+        check_len(buf, consts::IF_ADDR_MSG_SIZE, "AddressMessage")?;
+
+        Ok(Self {
+            family: buf[0],
+            prefix_len: buf[1],
+            flags: buf[2],
+            scope: buf[3],
+            index: i32::from_ne_bytes(buf[4..8].try_into()?),
+        })
     }
 }
 
@@ -284,6 +380,230 @@ impl RouteMessage {
     }
 
     pub fn deserialize(buf: &[u8]) -> Result<Self> {
-        Ok(unsafe { *(buf[..consts::ROUTE_MSG_SIZE].as_ptr() as *const Self) })
+        check_len(buf, consts::ROUTE_MSG_SIZE, "RouteMessage")?;
+
+        Ok(Self {
+            family: buf[0],
+            dst_len: buf[1],
+            src_len: buf[2],
+            tos: buf[3],
+            table: buf[4],
+            protocol: buf[5],
+            scope: buf[6],
+            rtm_type: buf[7],
+            flags: u32::from_ne_bytes(buf[8..12].try_into()?),
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NeighborMessage {
+    pub family: u8,
+    pub _pad1: u8,
+    pub _pad2: u16,
+    pub index: i32,
+    pub state: u16,
+    pub flags: u8,
+    pub neigh_type: u8,
+}
+
+impl NetlinkRequestData for NeighborMessage {
+    fn len(&self) -> usize {
+        consts::NEIGH_MSG_SIZE
+    }
+
+    fn is_empty(&self) -> bool {
+        self.family == 0
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(consts::NEIGH_MSG_SIZE);
+        buf.push(self.family);
+        buf.push(self._pad1);
+        buf.extend_from_slice(&self._pad2.to_ne_bytes());
+        buf.extend_from_slice(&self.index.to_ne_bytes());
+        buf.extend_from_slice(&self.state.to_ne_bytes());
+        buf.push(self.flags);
+        buf.push(self.neigh_type);
+        Ok(buf)
+    }
+}
+
+impl NeighborMessage {
+    pub fn new(family: i32) -> Self {
+        Self {
+            family: family as u8,
+            ..Default::default()
+        }
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        check_len(buf, consts::NEIGH_MSG_SIZE, "NeighborMessage")?;
+
+        Ok(Self {
+            family: buf[0],
+            index: i32::from_ne_bytes(buf[4..8].try_into()?),
+            state: u16::from_ne_bytes(buf[8..10].try_into()?),
+            flags: buf[10],
+            neigh_type: buf[11],
+            ..Default::default()
+        })
+    }
+}
+
+fn check_len(buf: &[u8], len: usize, name: &str) -> Result<()> {
+    if buf.len() < len {
+        bail!("{} needs {} bytes, got {}", name, len, buf.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_info_message_serialize() {
+        let msg = InfoMessage {
+            family: libc::AF_UNSPEC as u8,
+            ifi_type: 772,
+            index: 1,
+            flags: consts::IFF_UP | consts::IFF_LOOPBACK,
+            change: consts::IFF_UP,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            msg.serialize().unwrap(),
+            vec![
+                0x00, 0x00, 0x04, 0x03, 0x01, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x01, 0x00,
+                0x00, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_address_message_serialize() {
+        let msg = AddressMessage {
+            family: libc::AF_INET as u8,
+            prefix_len: 24,
+            flags: 0x80,
+            scope: libc::RT_SCOPE_LINK,
+            index: 0x0102,
+        };
+
+        assert_eq!(
+            msg.serialize().unwrap(),
+            vec![0x02, 0x18, 0x80, 0xFD, 0x02, 0x01, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_neighbor_message_serialize() {
+        let msg = NeighborMessage {
+            family: libc::AF_INET as u8,
+            index: 0x0102,
+            state: libc::NUD_PERMANENT,
+            flags: 0x02,
+            neigh_type: libc::RTN_UNICAST,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            msg.serialize().unwrap(),
+            vec![0x02, 0x00, 0x00, 0x00, 0x02, 0x01, 0x00, 0x00, 0x80, 0x00, 0x02, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_truncated_message() {
+        // header claims 32 bytes but only 20 are present
+        let buf = [
+            0x20, 0x00, 0x00, 0x00, 0x10, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert!(NetlinkMessage::from(&buf).is_err());
+    }
+
+    #[test]
+    fn test_truncated_attr() {
+        // attribute claims 12 bytes but only 8 are present
+        let buf = [0x0C, 0x00, 0x03, 0x00, 0x6C, 0x6F, 0x00, 0x00];
+
+        assert!(NetlinkRouteAttr::from(&buf).is_err());
+        assert!(NetlinkRouteAttr::map(&buf).is_err());
+    }
+
+    #[test]
+    fn test_zero_length_message() {
+        let mut buf = [0x00; 20];
+        assert!(NetlinkMessage::from(&buf).is_err());
+
+        // shorter than the header itself
+        buf[0] = 0x08;
+        assert!(NetlinkMessage::from(&buf).is_err());
+    }
+
+    #[test]
+    fn test_zero_length_attr() {
+        let mut buf = [0x00, 0x00, 0x03, 0x00, 0x6C, 0x6F, 0x00, 0x00];
+        assert!(NetlinkRouteAttr::from(&buf).is_err());
+        assert!(NetlinkRouteAttr::map(&buf).is_err());
+
+        // shorter than the attribute header itself
+        buf[0] = 0x02;
+        assert!(NetlinkRouteAttr::from(&buf).is_err());
+        assert!(NetlinkRouteAttr::map(&buf).is_err());
+    }
+
+    #[test]
+    fn test_truncated_struct() {
+        let buf = [0x00; 6];
+
+        assert!(InfoMessage::deserialize(&buf).is_err());
+        assert!(AddressMessage::deserialize(&buf).is_err());
+        assert!(RouteMessage::deserialize(&buf).is_err());
+        assert!(NeighborMessage::deserialize(&buf).is_err());
+    }
+
+    #[test]
+    fn test_unpadded_last_attr() {
+        // the final attribute may omit its trailing padding
+        let buf = [0x07, 0x00, 0x03, 0x00, 0x6C, 0x6F, 0x00];
+
+        let attrs = NetlinkRouteAttr::from(&buf).unwrap();
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(attrs[0].value, vec![0x6C, 0x6F, 0x00]);
+    }
+
+    #[test]
+    fn test_attr_iter() {
+        // IFLA_IFNAME "lo" followed by IFLA_MTU 65536
+        let buf = [
+            0x07, 0x00, 0x03, 0x00, 0x6C, 0x6F, 0x00, 0x00, 0x08, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x01, 0x00,
+        ];
+
+        let attrs = NetlinkRouteAttr::iter(&buf)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].0.rta_type, libc::IFLA_IFNAME);
+        assert_eq!(attrs[0].1, &[0x6C, 0x6F, 0x00]);
+        assert_eq!(attrs[1].0.rta_type, libc::IFLA_MTU);
+        // values borrow from the original buffer
+        assert_eq!(attrs[1].1.as_ptr(), buf[12..].as_ptr());
+
+        // iteration stops after a malformed attribute
+        let mut iter = NetlinkRouteAttr::iter(&buf[..12]);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
     }
 }
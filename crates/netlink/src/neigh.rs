@@ -0,0 +1,89 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+
+use crate::{
+    consts,
+    message::{NeighborMessage, NetlinkRouteAttr},
+    request::NetlinkRequestData,
+    utils::{parse_bytes, parse_i32, parse_u16, vec_to_addr},
+};
+
+#[derive(Default, Debug, Clone)]
+pub struct Neighbor {
+    pub index: i32,
+    pub family: u8,
+    pub ip: Option<IpAddr>,
+    pub mac: Vec<u8>,
+    pub state: u16,
+    pub flags: u8,
+    pub neigh_type: u8,
+}
+
+pub enum NeighEvent {
+    New(Neighbor),
+    Del(Neighbor),
+}
+
+pub fn neigh_deserialize(buf: &[u8]) -> Result<Neighbor> {
+    let neigh_msg = NeighborMessage::deserialize(buf)?;
+    let rt_attrs = NetlinkRouteAttr::from(&buf[neigh_msg.len()..])?;
+
+    let mut neigh = Neighbor {
+        index: neigh_msg.index,
+        family: neigh_msg.family,
+        state: neigh_msg.state,
+        flags: neigh_msg.flags,
+        neigh_type: neigh_msg.neigh_type,
+        ..Default::default()
+    };
+
+    for attr in rt_attrs {
+        match attr.rt_attr.rta_type {
+            libc::NDA_DST => {
+                neigh.ip = Some(vec_to_addr(attr.value)?);
+            }
+            libc::NDA_LLADDR => {
+                neigh.mac = attr.value;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(neigh)
+}
+
+// an entry of a bridge's forwarding database, as in `bridge fdb show`
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FdbEntry {
+    pub index: i32,
+    pub mac: [u8; 6],
+    pub vlan: Option<u16>,
+    pub master_index: i32,
+    pub state: u16,
+    pub flags: u8,
+}
+
+pub fn fdb_deserialize(buf: &[u8]) -> Result<FdbEntry> {
+    let neigh_msg = NeighborMessage::deserialize(buf)?;
+
+    let mut entry = FdbEntry {
+        index: neigh_msg.index,
+        state: neigh_msg.state,
+        flags: neigh_msg.flags,
+        ..Default::default()
+    };
+
+    for attr in NetlinkRouteAttr::iter(&buf[neigh_msg.len()..]) {
+        let (rt_attr, value) = attr?;
+
+        match rt_attr.rta_type {
+            libc::NDA_LLADDR => entry.mac = parse_bytes(value)?,
+            libc::NDA_VLAN => entry.vlan = Some(parse_u16(value)?),
+            consts::NDA_MASTER => entry.master_index = parse_i32(value)?,
+            _ => {}
+        }
+    }
+
+    Ok(entry)
+}
@@ -1,11 +1,16 @@
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    net::IpAddr,
+    os::fd::RawFd,
+};
 
 use anyhow::Result;
 
 use crate::{
     addr::{AddrCmd, Address},
-    handle::SocketHandle,
-    link::{Link, LinkAttrs},
+    handle::{self, SocketHandle, Subscription},
+    link::{BrPortOptions, Kind, Link, LinkAttrs, LinkEvent, Namespace},
+    neigh::{FdbEntry, NeighEvent, Neighbor},
     route::{Route, RtCmd},
 };
 
@@ -25,49 +30,184 @@ impl Netlink {
         Ok(Self { sockets })
     }
 
+    fn handle(&mut self, protocol: i32) -> Result<&mut SocketHandle> {
+        match self.sockets.entry(protocol) {
+            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Vacant(e) => Ok(e.insert(SocketHandle::new(protocol)?)),
+        }
+    }
+
     pub fn link_get(&mut self, attr: &LinkAttrs) -> Result<Box<dyn Link>> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_get(attr)
+        self.handle(libc::NETLINK_ROUTE)?.link_get(attr)
+    }
+
+    pub fn link_get_kind(&mut self, attr: &LinkAttrs) -> Result<Kind> {
+        self.handle(libc::NETLINK_ROUTE)?.link_get_kind(attr)
+    }
+
+    pub fn link_get_by_index(&mut self, index: i32) -> Result<Box<dyn Link>> {
+        self.handle(libc::NETLINK_ROUTE)?.link_get_by_index(index)
+    }
+
+    pub fn link_get_by_name(&mut self, name: &str) -> Result<Box<dyn Link>> {
+        self.handle(libc::NETLINK_ROUTE)?.link_get_by_name(name)
+    }
+
+    pub fn link_list(&mut self) -> Result<Vec<Box<dyn Link>>> {
+        self.handle(libc::NETLINK_ROUTE)?.link_list()
+    }
+
+    pub fn link_list_by_kind(&mut self, kind: &str) -> Result<Vec<Box<dyn Link>>> {
+        self.handle(libc::NETLINK_ROUTE)?.link_list_by_kind(kind)
     }
 
     pub fn link_add(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
         let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_new(link, flags)
+        self.handle(libc::NETLINK_ROUTE)?.link_new(link, flags)
+    }
+
+    pub fn link_add_if_absent(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_add_if_absent(link)
+    }
+
+    pub fn link_add_get(&mut self, link: &(impl Link + ?Sized)) -> Result<Box<dyn Link>> {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+        self.handle(libc::NETLINK_ROUTE)?.link_add_get(link, flags)
+    }
+
+    pub fn veth_add(&mut self, name: &str, peer: &str, peer_ns: Option<Namespace>) -> Result<()> {
+        let link = Kind::Veth {
+            attrs: LinkAttrs::new().with_name(name),
+            peer_name: peer.to_string(),
+            peer_hw_addr: None,
+            peer_ns,
+            peer_index: None,
+        };
+
+        self.link_add(&link)
     }
 
     pub fn link_modify(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+        self.handle(libc::NETLINK_ROUTE)?
             .link_new(link, libc::NLM_F_ACK)
     }
 
     pub fn link_del(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_del(link)
+        self.handle(libc::NETLINK_ROUTE)?.link_del(link)
     }
 
     pub fn link_setup(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .link_setup(link)
+        self.handle(libc::NETLINK_ROUTE)?.link_setup(link)
+    }
+
+    pub fn link_set_down(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_down(link)
+    }
+
+    pub fn link_set_promisc(&mut self, link: &(impl Link + ?Sized), on: bool) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_promisc(link, on)
+    }
+
+    pub fn link_set_allmulti(&mut self, link: &(impl Link + ?Sized), on: bool) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .link_set_allmulti(link, on)
+    }
+
+    pub fn link_set_mtu(&mut self, link: &(impl Link + ?Sized), mtu: u32) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_mtu(link, mtu)
+    }
+
+    pub fn link_set_txqlen(&mut self, link: &(impl Link + ?Sized), len: i32) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_txqlen(link, len)
+    }
+
+    pub fn link_set_group(&mut self, link: &(impl Link + ?Sized), group: u32) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .link_set_group(link, group)
+    }
+
+    pub fn link_set_alias(&mut self, link: &(impl Link + ?Sized), alias: &str) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .link_set_alias(link, alias)
+    }
+
+    pub fn link_get_master(
+        &mut self,
+        link: &(impl Link + ?Sized),
+    ) -> Result<Option<Box<dyn Link>>> {
+        self.handle(libc::NETLINK_ROUTE)?.link_get_master(link)
+    }
+
+    pub fn link_set_master(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        master_index: i32,
+    ) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .link_set_master(link, master_index)
+    }
+
+    pub fn link_set_nomaster(&mut self, link: &(impl Link + ?Sized)) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_nomaster(link)
+    }
+
+    pub fn link_set_name(&mut self, link: &(impl Link + ?Sized), name: &str) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_name(link, name)
+    }
+
+    pub fn link_set_ns_fd(&mut self, link: &(impl Link + ?Sized), fd: RawFd) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_ns_fd(link, fd)
+    }
+
+    pub fn link_set_ns_pid(&mut self, link: &(impl Link + ?Sized), pid: i32) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.link_set_ns_pid(link, pid)
+    }
+
+    pub fn link_set_brport(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        opts: &BrPortOptions,
+    ) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .link_set_brport(link, opts)
+    }
+
+    pub fn bridge_vlan_add(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        vid: u16,
+        pvid: bool,
+        untagged: bool,
+    ) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .bridge_vlan_add(link, vid, pvid, untagged)
+    }
+
+    pub fn bridge_vlan_del(&mut self, link: &(impl Link + ?Sized), vid: u16) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.bridge_vlan_del(link, vid)
+    }
+
+    pub fn link_subscribe(&self) -> Result<Subscription<LinkEvent>> {
+        SocketHandle::link_subscribe()
+    }
+
+    pub fn neigh_subscribe(&self) -> Result<Subscription<NeighEvent>> {
+        SocketHandle::neigh_subscribe()
     }
 
     pub fn addr_show(&mut self, link: &(impl Link + ?Sized)) -> Result<Vec<Address>> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+        self.handle(libc::NETLINK_ROUTE)?
             .addr_show(link, libc::AF_UNSPEC)
     }
 
+    pub fn addr_show_family(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        family: i32,
+    ) -> Result<Vec<Address>> {
+        self.handle(libc::NETLINK_ROUTE)?.addr_show(link, family)
+    }
+
     pub fn addr_handle(
         &mut self,
         command: AddrCmd,
@@ -87,17 +227,59 @@ impl Netlink {
             AddrCmd::Del => (libc::RTM_DELADDR, libc::NLM_F_ACK),
         };
 
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
+        self.handle(libc::NETLINK_ROUTE)?
             .addr_handle(link, addr, proto, flags)
     }
 
+    pub fn neigh_add(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.neigh_add(neigh)
+    }
+
+    pub fn neigh_del(&mut self, neigh: &Neighbor) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?.neigh_del(neigh)
+    }
+
+    pub fn neigh_list(&mut self, link: &(impl Link + ?Sized)) -> Result<Vec<Neighbor>> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .neigh_list(link.attrs().index, libc::AF_UNSPEC)
+    }
+
+    pub fn fdb_add(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mac: [u8; 6],
+        dst: Option<IpAddr>,
+        vlan: Option<u16>,
+    ) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .fdb_add(link, mac, dst, vlan)
+    }
+
+    pub fn fdb_del(
+        &mut self,
+        link: &(impl Link + ?Sized),
+        mac: [u8; 6],
+        dst: Option<IpAddr>,
+        vlan: Option<u16>,
+    ) -> Result<()> {
+        self.handle(libc::NETLINK_ROUTE)?
+            .fdb_del(link, mac, dst, vlan)
+    }
+
+    pub fn fdb_list(&mut self, link: &(impl Link + ?Sized)) -> Result<Vec<FdbEntry>> {
+        self.handle(libc::NETLINK_ROUTE)?.fdb_list(link)
+    }
+
     pub fn route_get(&mut self, dst: &IpAddr) -> Result<Vec<Route>> {
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .route_get(dst)
+        self.handle(libc::NETLINK_ROUTE)?.route_get(dst)
+    }
+
+    pub fn route_list(&mut self, family: i32) -> Result<Vec<Route>> {
+        self.handle(libc::NETLINK_ROUTE)?.route_list(family)
+    }
+
+    pub fn route_get_to(&mut self, dst: IpAddr) -> Result<Route> {
+        self.handle(libc::NETLINK_ROUTE)?.route_get_to(dst)
     }
 
     pub fn route_handle(&mut self, command: RtCmd, route: &Route) -> Result<()> {
@@ -117,16 +299,36 @@ impl Netlink {
             RtCmd::Del => (libc::RTM_DELROUTE, libc::NLM_F_ACK),
         };
 
-        self.sockets
-            .entry(libc::NETLINK_ROUTE)
-            .or_insert(SocketHandle::new(libc::NETLINK_ROUTE)?)
-            .route_handle(route, proto, flags)
+        let res = self
+            .handle(libc::NETLINK_ROUTE)?
+            .route_handle(route, proto, flags);
+
+        match (command, res) {
+            // deleting a route that is already gone is not an error
+            (RtCmd::Del, Err(e))
+                if matches!(handle::errno(&e), Some(libc::ESRCH) | Some(libc::ENOENT)) =>
+            {
+                Ok(())
+            }
+            (_, res) => res,
+        }
+    }
+
+    pub fn route_ensure(&mut self, route: &Route) -> Result<()> {
+        match self.route_handle(RtCmd::Add, route) {
+            Err(e) if handle::errno(&e) == Some(libc::EEXIST) => {
+                self.route_handle(RtCmd::Replace, route)
+            }
+            res => res,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{addr::AddrCmd, link::Kind};
+    use std::os::fd::AsRawFd;
+
+    use crate::addr::AddrCmd;
 
     use super::*;
 
@@ -147,15 +349,30 @@ mod tests {
         assert_eq!(nl.sockets.len(), SUPPORTED_PROTOCOLS.len());
     }
 
+    #[test]
+    fn test_socket_reuse() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let attr = LinkAttrs::new().with_name("lo");
+
+        let handle = &netlink.sockets[&libc::NETLINK_ROUTE];
+        let (fd, seq) = (handle.socket.as_raw_fd(), handle.seq);
+
+        netlink.link_get(&attr).unwrap();
+        netlink.link_get(&attr).unwrap();
+
+        let handle = &netlink.sockets[&libc::NETLINK_ROUTE];
+        assert_eq!(handle.socket.as_raw_fd(), fd);
+        assert_eq!(handle.seq, seq.wrapping_add(2));
+    }
+
     #[test]
     fn test_link_add_modify_del() {
         test_setup!();
         let mut netlink = Netlink::new().unwrap();
 
-        let dummy = Kind::Dummy(LinkAttrs {
-            name: "foo".to_string(),
-            ..Default::default()
-        });
+        let dummy = Kind::Dummy(LinkAttrs::new().with_name("foo"));
 
         netlink.link_add(&dummy).unwrap();
 
@@ -175,15 +392,47 @@ mod tests {
         assert!(link.is_some());
     }
 
+    #[test]
+    fn test_link_add_if_absent() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let dummy = Kind::Dummy(LinkAttrs::new().with_name("foo"));
+
+        netlink.link_add_if_absent(&dummy).unwrap();
+        netlink.link_add_if_absent(&dummy).unwrap();
+
+        assert!(netlink.link_add(&dummy).is_err());
+
+        let link = netlink.link_get(dummy.attrs()).unwrap();
+        assert_eq!(link.link_type(), "dummy");
+    }
+
+    #[test]
+    fn test_veth_add() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        netlink.veth_add("foo", "bar", None).unwrap();
+
+        let link = netlink
+            .link_get(&LinkAttrs::new().with_name("foo"))
+            .unwrap();
+        assert_eq!(link.link_type(), "veth");
+
+        let peer = netlink
+            .link_get(&LinkAttrs::new().with_name("bar"))
+            .unwrap();
+        assert_eq!(peer.link_type(), "veth");
+        assert_eq!(peer.attrs().parent_index, link.attrs().index);
+    }
+
     #[test]
     fn test_addr_add_replace_del() {
         test_setup!();
         let mut netlink = Netlink::new().unwrap();
 
-        let dummy = Kind::Dummy(LinkAttrs {
-            name: "foo".to_string(),
-            ..Default::default()
-        });
+        let dummy = Kind::Dummy(LinkAttrs::new().with_name("foo"));
 
         netlink.link_add(&dummy).unwrap();
 
@@ -220,10 +469,7 @@ mod tests {
         test_setup!();
         let mut netlink = Netlink::new().unwrap();
 
-        let attr = LinkAttrs {
-            name: "lo".to_string(),
-            ..Default::default()
-        };
+        let attr = LinkAttrs::new().with_name("lo");
 
         let link = netlink.link_get(&attr).unwrap();
 
@@ -249,4 +495,93 @@ mod tests {
         let res = netlink.route_get(&route.dst.unwrap().addr()).err();
         assert!(res.is_some());
     }
+
+    #[test]
+    fn test_route_list() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let link = netlink.link_get_by_name("lo").unwrap();
+
+        netlink.link_setup(&link).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+
+        netlink.route_handle(RtCmd::Add, &route).unwrap();
+
+        let routes = netlink.route_list(libc::AF_INET).unwrap();
+        assert!(routes
+            .iter()
+            .any(|r| r.dst == route.dst && r.oif_index == link.attrs().index));
+
+        let routes = netlink.route_list(libc::AF_INET6).unwrap();
+        assert!(routes.iter().all(|r| r.family == libc::AF_INET6 as u8));
+    }
+
+    #[test]
+    fn test_route_ensure() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let attr = LinkAttrs::new().with_name("lo");
+
+        let link = netlink.link_get(&attr).unwrap();
+
+        netlink.link_setup(&link).unwrap();
+
+        let mut route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            src: Some("127.1.1.1".parse().unwrap()),
+            ..Default::default()
+        };
+
+        netlink.route_ensure(&route).unwrap();
+
+        route.src = Some("127.1.1.2".parse().unwrap());
+        netlink.route_ensure(&route).unwrap();
+        netlink.route_ensure(&route).unwrap();
+
+        let res = netlink
+            .route_get(&route.dst.unwrap().addr())
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.dst == route.dst)
+            .collect::<Vec<_>>();
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].src, route.src);
+    }
+
+    #[test]
+    fn test_route_del_not_found() {
+        test_setup!();
+        let mut netlink = Netlink::new().unwrap();
+
+        let attr = LinkAttrs::new().with_name("lo");
+
+        let link = netlink.link_get(&attr).unwrap();
+
+        netlink.link_setup(&link).unwrap();
+
+        let route = Route {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+
+        netlink.route_handle(RtCmd::Del, &route).unwrap();
+
+        // the socket handle still surfaces the kernel's error
+        let err = netlink
+            .handle(libc::NETLINK_ROUTE)
+            .unwrap()
+            .route_handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap_err();
+        assert_eq!(handle::errno(&err), Some(libc::ESRCH));
+    }
 }
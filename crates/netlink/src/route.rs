@@ -1,12 +1,13 @@
 use std::net::IpAddr;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use ipnet::IpNet;
 
 use crate::{
+    consts,
     message::{NetlinkRouteAttr, RouteMessage},
     request::NetlinkRequestData,
-    utils::vec_to_addr,
+    utils::{align_of, parse_u32, vec_to_addr},
 };
 
 pub enum RtCmd {
@@ -25,11 +26,163 @@ pub struct Route {
     pub src: Option<IpAddr>,
     pub gw: Option<IpAddr>,
     pub tos: u8,
-    pub table: u8,
+    pub table: u32,
     pub protocol: u8,
     pub scope: u8,
     pub rtm_type: u8,
     pub flags: u32,
+    // sets RTNH_F_ONLINK so a gateway outside the interface's subnets is accepted
+    pub onlink: bool,
+    pub priority: Option<u32>,
+    pub multipath: Vec<NextHop>,
+    pub metrics: RouteMetrics,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct RouteMetrics {
+    pub mtu: Option<u32>,
+    pub advmss: Option<u32>,
+    pub hoplimit: Option<u32>,
+}
+
+impl RouteMetrics {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // the nested RTA_METRICS attribute, or None when no metric is set
+    pub fn to_attr(&self) -> Option<NetlinkRouteAttr> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut attr = NetlinkRouteAttr::new(libc::RTA_METRICS, vec![]);
+
+        for (rtax, value) in [
+            (consts::RTAX_MTU, self.mtu),
+            (consts::RTAX_ADVMSS, self.advmss),
+            (consts::RTAX_HOPLIMIT, self.hoplimit),
+        ] {
+            if let Some(value) = value {
+                attr.add_child(rtax, value.to_ne_bytes().to_vec());
+            }
+        }
+
+        Some(attr)
+    }
+
+    fn parse(buf: &[u8]) -> Result<Self> {
+        let mut metrics = Self::default();
+
+        for attr in NetlinkRouteAttr::iter(buf) {
+            let (rt_attr, value) = attr?;
+
+            match rt_attr.rta_type {
+                consts::RTAX_MTU => metrics.mtu = Some(parse_u32(value)?),
+                consts::RTAX_ADVMSS => metrics.advmss = Some(parse_u32(value)?),
+                consts::RTAX_HOPLIMIT => metrics.hoplimit = Some(parse_u32(value)?),
+                _ => {}
+            }
+        }
+
+        Ok(metrics)
+    }
+}
+
+impl Route {
+    // derives the address family from dst, src, gw and the multipath gateways,
+    // falling back to the family field when none of them is set
+    pub fn address_family(&self) -> Result<u8> {
+        let family = |ip: &IpAddr| match ip {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        };
+
+        let mut res: Option<(&str, u8)> = None;
+
+        let addrs = self
+            .dst
+            .map(|dst| ("dst", dst.addr()))
+            .into_iter()
+            .chain(self.src.map(|src| ("src", src)))
+            .chain(self.gw.map(|gw| ("gw", gw)))
+            .chain(self.multipath.iter().map(|hop| ("multipath gw", hop.gw)));
+
+        for (name, ip) in addrs {
+            match res {
+                None => res = Some((name, family(&ip))),
+                Some((first, f)) if f != family(&ip) => {
+                    bail!("route {} and {} address family mismatch", first, name);
+                }
+                _ => {}
+            }
+        }
+
+        match res {
+            Some((_, f)) if self.family != 0 && self.family != f => {
+                bail!("route family {} doesn't match its addresses", self.family);
+            }
+            Some((_, f)) => Ok(f),
+            None => Ok(self.family),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextHop {
+    pub gw: IpAddr,
+    pub index: i32,
+    // relative weight of this next-hop, the kernel stores it as `rtnh_hops + 1`
+    pub weight: u8,
+}
+
+impl NextHop {
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let gw_data = match self.gw {
+            IpAddr::V4(ip) => ip.octets().to_vec(),
+            IpAddr::V6(ip) => ip.octets().to_vec(),
+        };
+        let gw = NetlinkRouteAttr::new(libc::RTA_GATEWAY, gw_data).serialize()?;
+
+        let len = consts::RT_NEXTHOP_SIZE + gw.len();
+
+        let mut buf = Vec::with_capacity(len);
+        buf.extend_from_slice(&(len as u16).to_ne_bytes());
+        buf.push(0);
+        buf.push(self.weight.saturating_sub(1));
+        buf.extend_from_slice(&self.index.to_ne_bytes());
+        buf.extend_from_slice(&gw);
+
+        Ok(buf)
+    }
+
+    fn from(mut buf: &[u8]) -> Result<Vec<Self>> {
+        let mut hops = Vec::new();
+
+        while buf.len() >= consts::RT_NEXTHOP_SIZE {
+            let len = u16::from_ne_bytes(buf[..2].try_into()?) as usize;
+            if len < consts::RT_NEXTHOP_SIZE || len > buf.len() {
+                bail!("invalid rtnexthop length: {}", len);
+            }
+
+            let weight = buf[3].saturating_add(1);
+            let index = i32::from_ne_bytes(buf[4..8].try_into()?);
+
+            for attr in NetlinkRouteAttr::from(&buf[consts::RT_NEXTHOP_SIZE..len])? {
+                if attr.rt_attr.rta_type == libc::RTA_GATEWAY {
+                    hops.push(Self {
+                        gw: vec_to_addr(attr.value)?,
+                        index,
+                        weight,
+                    });
+                }
+            }
+
+            buf = &buf[align_of(len, consts::RTA_ALIGNTO).min(buf.len())..];
+        }
+
+        Ok(hops)
+    }
 }
 
 pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
@@ -39,10 +192,12 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
     let mut route = Route {
         family: if_route_msg.family,
         tos: if_route_msg.tos,
-        table: if_route_msg.table,
+        table: if_route_msg.table as u32,
         protocol: if_route_msg.protocol,
         scope: if_route_msg.scope,
         rtm_type: if_route_msg.rtm_type,
+        flags: if_route_msg.flags,
+        onlink: if_route_msg.flags & consts::RTNH_F_ONLINK != 0,
         ..Default::default()
     };
 
@@ -63,6 +218,18 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
             libc::RTA_IIF => {
                 route.iif_index = i32::from_ne_bytes(attr.value[..4].try_into()?);
             }
+            libc::RTA_TABLE => {
+                route.table = parse_u32(&attr.value)?;
+            }
+            libc::RTA_PRIORITY => {
+                route.priority = Some(parse_u32(&attr.value)?);
+            }
+            libc::RTA_MULTIPATH => {
+                route.multipath = NextHop::from(&attr.value)?;
+            }
+            libc::RTA_METRICS => {
+                route.metrics = RouteMetrics::parse(&attr.value)?;
+            }
             // TODO: more types
             _ => {}
         }
@@ -70,3 +237,89 @@ pub fn route_deserialize(buf: &[u8]) -> Result<Route> {
 
     Ok(route)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 192.168.0.0/24 dev 3 table 300 metric 100
+    static ROUTE_MSG: [u8; 44] = [
+        0x02, 0x18, 0x00, 0x00, 0xFC, 0x03, 0xFD, 0x01, 0x10, 0x00, 0x00, 0x00, 0x08, 0x00, 0x0F,
+        0x00, 0x2C, 0x01, 0x00, 0x00, 0x08, 0x00, 0x01, 0x00, 0xC0, 0xA8, 0x00, 0x00, 0x08, 0x00,
+        0x06, 0x00, 0x64, 0x00, 0x00, 0x00, 0x08, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_route_deserialize() {
+        let route = route_deserialize(&ROUTE_MSG).unwrap();
+
+        assert_eq!(route.family, libc::AF_INET as u8);
+        assert_eq!(route.dst, Some("192.168.0.0/24".parse().unwrap()));
+        assert_eq!(route.oif_index, 3);
+        // tables above 255 only fit in RTA_TABLE, the header says RT_TABLE_COMPAT
+        assert_eq!(route.table, 300);
+        assert_eq!(route.priority, Some(100));
+        assert_eq!(route.scope, libc::RT_SCOPE_LINK);
+    }
+
+    #[test]
+    fn test_route_address_family() {
+        let cases = [
+            (
+                Some("10.0.0.0/24"),
+                Some("10.0.0.1"),
+                Some("10.0.0.254"),
+                Some(libc::AF_INET),
+            ),
+            (None, Some("10.0.0.1"), None, Some(libc::AF_INET)),
+            (
+                None,
+                Some("fd00::1"),
+                Some("fd00::fe"),
+                Some(libc::AF_INET6),
+            ),
+            (None, Some("fd00::1"), Some("10.0.0.254"), None),
+            (Some("10.0.0.0/24"), Some("fd00::1"), None, None),
+            (Some("fd00::/64"), None, Some("10.0.0.254"), None),
+            (None, None, None, Some(libc::AF_UNSPEC)),
+        ];
+
+        for (dst, src, gw, expected) in cases {
+            let route = Route {
+                dst: dst.map(|v| v.parse().unwrap()),
+                src: src.map(|v| v.parse().unwrap()),
+                gw: gw.map(|v| v.parse().unwrap()),
+                ..Default::default()
+            };
+
+            match expected {
+                Some(family) => assert_eq!(route.address_family().unwrap(), family as u8),
+                None => assert!(route.address_family().is_err()),
+            }
+        }
+
+        let route = Route {
+            gw: Some("10.0.0.254".parse().unwrap()),
+            multipath: vec![NextHop {
+                gw: "fd00::fe".parse().unwrap(),
+                index: 1,
+                weight: 1,
+            }],
+            ..Default::default()
+        };
+
+        let err = route.address_family().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "route gw and multipath gw address family mismatch"
+        );
+
+        let route = Route {
+            family: libc::AF_INET6 as u8,
+            gw: Some("10.0.0.254".parse().unwrap()),
+            ..Default::default()
+        };
+
+        assert!(route.address_family().is_err());
+    }
+}
@@ -1,13 +1,17 @@
 use std::{
-    io::{Error, Result},
-    os::fd::RawFd,
+    io::{Error, ErrorKind, Result},
+    os::fd::{AsRawFd, RawFd},
+    time::Duration,
 };
 
-use crate::{consts, message::NetlinkMessage};
+use crate::{consts, message::NetlinkMessage, utils::align_of};
 
 pub struct NetlinkSocket {
     fd: RawFd,
     lsa: SockAddrNetlink,
+    // reused across receives and only ever grown, so a dump doesn't
+    // allocate and zero a fresh buffer for every datagram
+    buf: Vec<u8>,
 }
 
 impl NetlinkSocket {
@@ -23,11 +27,31 @@ impl NetlinkSocket {
             return Err(Error::last_os_error());
         }
         let lsa = SockAddrNetlink::new(pid, groups);
-        let s = Self { fd, lsa };
+        let buf = vec![0; align_of(consts::RECV_BUF_SIZE, page_size())];
+        let s = Self { fd, lsa, buf };
         s.bind()?;
+        // extended acks are best effort, older kernels reject the option
+        let _ = s.set_ext_ack(true);
         Ok(s)
     }
 
+    fn set_ext_ack(&self, enable: bool) -> Result<()> {
+        let val = enable as libc::c_int;
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_NETLINK,
+                consts::NETLINK_EXT_ACK,
+                &val as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
     fn bind(&self) -> Result<()> {
         let (addr, addr_len) = self.lsa.as_raw();
         let ret = unsafe { libc::bind(self.fd, addr, addr_len) };
@@ -38,7 +62,10 @@ impl NetlinkSocket {
     }
 
     pub fn send(&self, buf: &[u8]) -> Result<()> {
-        let (addr, addr_len) = self.lsa.as_raw();
+        // always address the kernel, the bound address may carry an explicit
+        // pid or multicast groups which would loop the request back to us
+        let kernel = SockAddrNetlink::new(consts::PID_KERNEL, 0);
+        let (addr, addr_len) = kernel.as_raw();
         let buf_ptr = buf.as_ptr() as *const libc::c_void;
         let buf_len = buf.len() as libc::size_t;
         let ret = unsafe { libc::sendto(self.fd, buf_ptr, buf_len, 0, addr, addr_len) };
@@ -48,24 +75,113 @@ impl NetlinkSocket {
         Ok(())
     }
 
-    pub fn recv(&self) -> Result<(Vec<NetlinkMessage>, libc::sockaddr_nl)> {
+    pub fn recv(&mut self) -> Result<(Vec<NetlinkMessage>, libc::sockaddr_nl)> {
+        let mut buf = std::mem::take(&mut self.buf);
+
+        // peek with MSG_TRUNC first so that the real datagram size is reported
+        // even when it does not fit in the buffer, then grow it and read for real
+        let res = self
+            .recvfrom(&mut buf, libc::MSG_PEEK | libc::MSG_TRUNC)
+            .and_then(|(len, _)| {
+                if len > buf.len() {
+                    buf.resize(align_of(len, page_size()), 0);
+                }
+
+                self.recvfrom(&mut buf, 0)
+            })
+            .and_then(|(len, from)| Ok((NetlinkMessage::from(&buf[..len])?, from)));
+
+        self.buf = buf;
+        res
+    }
+
+    fn recvfrom(&self, buf: &mut [u8], flags: i32) -> Result<(usize, libc::sockaddr_nl)> {
         let mut from: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
-        let mut buf: [u8; consts::RECV_BUF_SIZE] = [0; consts::RECV_BUF_SIZE];
         let ret = unsafe {
             libc::recvfrom(
                 self.fd,
                 buf.as_mut_ptr() as *mut libc::c_void,
                 buf.len() as libc::size_t,
-                0,
+                flags,
                 &mut from as *mut _ as *mut libc::sockaddr,
                 &mut std::mem::size_of::<libc::sockaddr_nl>() as *mut _ as *mut libc::socklen_t,
             )
         };
+        if ret < 0 {
+            let err = Error::last_os_error();
+            if err.kind() == ErrorKind::WouldBlock {
+                return Err(Error::new(
+                    ErrorKind::TimedOut,
+                    "timed out waiting for netlink reply",
+                ));
+            }
+            return Err(err);
+        }
+        Ok((ret as usize, from))
+    }
+
+    pub fn set_timeout(&self, dur: Duration) -> Result<()> {
+        let tv = libc::timeval {
+            tv_sec: dur.as_secs() as libc::time_t,
+            tv_usec: dur.subsec_micros() as libc::suseconds_t,
+        };
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
         if ret < 0 {
             return Err(Error::last_os_error());
         }
-        let netlink_msgs = NetlinkMessage::from(&buf[..ret as usize])?;
-        Ok((netlink_msgs, from))
+        Ok(())
+    }
+
+    pub fn set_recv_buffer_size(&self, bytes: usize) -> Result<()> {
+        let size = bytes as libc::c_int;
+
+        // SO_RCVBUFFORCE bypasses net.core.rmem_max but needs CAP_NET_ADMIN
+        for opt in [libc::SO_RCVBUFFORCE, libc::SO_RCVBUF] {
+            let ret = unsafe {
+                libc::setsockopt(
+                    self.fd,
+                    libc::SOL_SOCKET,
+                    opt,
+                    &size as *const _ as *const libc::c_void,
+                    std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+                )
+            };
+            if ret == 0 {
+                return Ok(());
+            }
+
+            let err = Error::last_os_error();
+            if err.raw_os_error() != Some(libc::EPERM) {
+                return Err(err);
+            }
+        }
+
+        Err(Error::from_raw_os_error(libc::EPERM))
+    }
+
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        let mut size: libc::c_int = 0;
+        let ret = unsafe {
+            libc::getsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                &mut size as *mut _ as *mut libc::c_void,
+                &mut std::mem::size_of::<libc::c_int>() as *mut _ as *mut libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(size as usize)
     }
 
     pub fn pid(&self) -> Result<u32> {
@@ -84,12 +200,25 @@ impl NetlinkSocket {
     }
 }
 
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
 impl Drop for NetlinkSocket {
     fn drop(&mut self) {
         unsafe { libc::close(self.fd) };
     }
 }
 
+fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
 pub struct SockAddrNetlink(libc::sockaddr_nl);
 
 impl SockAddrNetlink {
@@ -156,7 +285,7 @@ mod tests {
 
     #[test]
     fn test_netlink_socket() {
-        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        let mut s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
 
         // This is a valid message for listing the network links on the system
         let msg = vec![
@@ -203,4 +332,55 @@ mod tests {
             let _ = InfoMessage::deserialize(r).unwrap();
         });
     }
+
+    #[test]
+    fn test_set_recv_buffer_size() {
+        if !nix::unistd::geteuid().is_root() {
+            eprintln!("Test skipped, must be run as root");
+            return;
+        }
+
+        let s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_recv_buffer_size(1 << 20).unwrap();
+
+        // the kernel doubles the requested value to leave room for bookkeeping
+        assert_eq!(s.recv_buffer_size().unwrap(), 2 << 20);
+    }
+
+    #[test]
+    fn test_recv_buffer_reused() {
+        let mut s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_timeout(Duration::from_millis(200)).unwrap();
+
+        let len = s.buf.len();
+        let ptr = s.buf.as_ptr();
+
+        assert!(len >= consts::RECV_BUF_SIZE);
+        assert_eq!(len % page_size(), 0);
+
+        // same RTM_GETLINK dump request as above
+        let msg = vec![
+            0x14, 0x00, 0x00, 0x00, 0x12, 0x00, 0x01, 0x03, 0xfd, 0xfe, 0x38, 0x5c, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        s.send(&msg[..]).unwrap();
+        s.recv().unwrap();
+
+        // a failed receive keeps the buffer as well
+        while s.recv().is_ok() {}
+
+        assert_eq!(s.buf.len(), len);
+        assert_eq!(s.buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_set_timeout() {
+        let mut s = NetlinkSocket::new(libc::NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_timeout(Duration::from_millis(200)).unwrap();
+
+        // nothing was sent, so the kernel never replies
+        let err = s.recv().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
 }
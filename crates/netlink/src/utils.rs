@@ -12,6 +12,45 @@ pub fn zero_terminated(s: &str) -> Vec<u8> {
     v
 }
 
+// reverse of zero_terminated, an empty value is an error rather than an out of
+// bounds slice
+pub fn parse_str(value: &[u8]) -> Result<&str> {
+    match value.split_last() {
+        Some((0, s)) => Ok(std::str::from_utf8(s)?),
+        Some(_) => Ok(std::str::from_utf8(value)?),
+        None => bail!("empty string attribute"),
+    }
+}
+
+// numeric attributes are read from the front of the value, a value shorter
+// than the type is an error rather than an out of bounds slice
+pub fn parse_bytes<const N: usize>(value: &[u8]) -> Result<[u8; N]> {
+    match value.get(..N) {
+        Some(bytes) => Ok(bytes.try_into()?),
+        None => bail!(
+            "attribute too short: expected {} bytes, got {}",
+            N,
+            value.len()
+        ),
+    }
+}
+
+pub fn parse_u8(value: &[u8]) -> Result<u8> {
+    Ok(u8::from_ne_bytes(parse_bytes(value)?))
+}
+
+pub fn parse_u16(value: &[u8]) -> Result<u16> {
+    Ok(u16::from_ne_bytes(parse_bytes(value)?))
+}
+
+pub fn parse_u32(value: &[u8]) -> Result<u32> {
+    Ok(u32::from_ne_bytes(parse_bytes(value)?))
+}
+
+pub fn parse_i32(value: &[u8]) -> Result<i32> {
+    Ok(i32::from_ne_bytes(parse_bytes(value)?))
+}
+
 pub fn vec_to_addr(vec: Vec<u8>) -> Result<IpAddr> {
     // TODO: use IpAddr::parse_ascii when to be stable
     match vec.len() {
@@ -28,3 +67,43 @@ pub fn vec_to_addr(vec: Vec<u8>) -> Result<IpAddr> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_str, vec_to_addr, zero_terminated};
+
+    #[test]
+    fn test_vec_to_addr_v4() {
+        let addr = vec_to_addr(vec![10, 244, 0, 1]).unwrap();
+        assert_eq!(addr, "10.244.0.1".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_vec_to_addr_v6() {
+        let mut vec = vec![0; 16];
+        vec[0] = 0xfe;
+        vec[1] = 0x80;
+        vec[15] = 0x01;
+
+        let addr = vec_to_addr(vec).unwrap();
+        assert_eq!(addr, "fe80::1".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_str() {
+        assert_eq!(parse_str(&zero_terminated("foo")).unwrap(), "foo");
+        assert_eq!(parse_str(b"foo").unwrap(), "foo");
+        assert_eq!(parse_str(&[0]).unwrap(), "");
+        assert!(parse_str(&[]).is_err());
+    }
+
+    #[test]
+    fn test_vec_to_addr_invalid_len() {
+        let err = vec_to_addr(vec![0x02, 0x42, 0xac, 0x11, 0x00, 0x02])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "invalid address length: 6");
+    }
+}
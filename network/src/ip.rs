@@ -1,5 +1,12 @@
-use anyhow::Result;
-use std::{collections::BTreeSet, net::Ipv4Addr};
+use anyhow::{bail, Result};
+use ipnet::Ipv6Net;
+use std::{
+    collections::BTreeSet,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+// IPv6 subnets with more host bits than this are too large to enumerate
+pub const NMAP6_MAX_HOST_BITS: u8 = 16;
 
 fn ip_to_binary(ip: Ipv4Addr) -> u32 {
     let octets = ip.octets();
@@ -13,7 +20,7 @@ pub fn nmap(subnet: &str) -> Result<BTreeSet<Ipv4Addr>> {
     let (ip, subnet_mask_size) = subnet.split_once('/').unwrap();
     let ip = ip.parse::<Ipv4Addr>()?;
     let subnet_mask_size = subnet_mask_size.parse::<u8>()?;
-    let subnet_mask = Ipv4Addr::from(u32::max_value() << (32 - subnet_mask_size));
+    let subnet_mask = Ipv4Addr::from(u32::MAX << (32 - subnet_mask_size));
 
     let ip = ip_to_binary(ip) & ip_to_binary(subnet_mask);
     let mut ips = BTreeSet::new();
@@ -25,11 +32,39 @@ pub fn nmap(subnet: &str) -> Result<BTreeSet<Ipv4Addr>> {
     Ok(ips)
 }
 
+// like nmap but without the network and broadcast addresses, which can't be
+// assigned to hosts. /31 and /32 subnets have neither, so they are left as is.
+pub fn nmap_hosts(subnet: &str) -> Result<BTreeSet<Ipv4Addr>> {
+    let mut ips = nmap(subnet)?;
+
+    if ips.len() > 2 {
+        ips.pop_first();
+        ips.pop_last();
+    }
+
+    Ok(ips)
+}
+
+pub fn nmap6(subnet: &str) -> Result<BTreeSet<Ipv6Addr>> {
+    let subnet = subnet.parse::<Ipv6Net>()?;
+    let host_bits = 128 - subnet.prefix_len();
+
+    if host_bits > NMAP6_MAX_HOST_BITS {
+        bail!("subnet {subnet} is too large to enumerate");
+    }
+
+    let ip = u128::from(subnet.network());
+
+    Ok((0..1u128 << host_bits)
+        .map(|i| Ipv6Addr::from(ip + i))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::Ipv4Addr;
 
-    use crate::ip::{ip_to_binary, nmap};
+    use crate::ip::{ip_to_binary, nmap, nmap6, nmap_hosts};
 
     #[test]
     fn ip_to_binary_test() {
@@ -46,4 +81,26 @@ mod tests {
 
         assert_eq!(ips.len(), 256);
     }
+
+    #[test]
+    fn nmap_hosts_test() {
+        let ips = nmap_hosts("10.244.0.0/24").unwrap();
+
+        assert_eq!(ips.len(), 254);
+        assert!(!ips.contains(&Ipv4Addr::new(10, 244, 0, 0)));
+        assert!(!ips.contains(&Ipv4Addr::new(10, 244, 0, 255)));
+
+        assert_eq!(nmap_hosts("10.244.0.0/31").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nmap6_test() {
+        let ips = nmap6("fd00:10:244::/120").unwrap();
+
+        assert_eq!(ips.len(), 256);
+        assert_eq!(ips.first().unwrap().to_string(), "fd00:10:244::");
+        assert_eq!(ips.last().unwrap().to_string(), "fd00:10:244::ff");
+
+        assert!(nmap6("fd00:10:244::/64").is_err());
+    }
 }
@@ -1,7 +1,15 @@
-use anyhow::{bail, Error, Result};
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::net::Ipv4Addr;
+use anyhow::{bail, Result};
+use ipnet::IpNet;
+use netlink::{
+    addr::{AddrCmd, Address},
+    link::{BondMode, Kind, Link, LinkAttrs},
+    netlink::Netlink,
+    route::{Route, RtCmd},
+};
+use std::{fs::File, net::IpAddr, os::fd::AsRawFd};
+
+// where `ip netns add` bind-mounts named network namespaces
+const NETNS_RUN_DIR: &str = "/var/run/netns";
 
 #[macro_export]
 macro_rules! run_command {
@@ -23,135 +31,156 @@ macro_rules! test_setup {
     };
 }
 
+// only kinds that can be created from a name alone are accepted, a veth peer
+// gets a kernel-chosen name. kinds that need a parent, a VNI, endpoints or a
+// mode have to be built as a Kind and added through Netlink
 pub fn add_link(if_name: &str, link_type: &str) -> Result<()> {
-    let out = run_command!("ip", "link", "add", if_name, "type", link_type);
+    let attrs = LinkAttrs {
+        name: if_name.to_string(),
+        ..Default::default()
+    };
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    let link = match link_type {
+        "bridge" => Kind::bridge(attrs),
+        "dummy" => Kind::Dummy(attrs),
+        "veth" => Kind::Veth {
+            attrs,
+            peer_name: String::new(),
+            peer_hw_addr: None,
+            peer_ns: None,
+            peer_index: None,
+        },
+        "bond" => Kind::Bond {
+            attrs,
+            mode: BondMode::BalanceRr,
+            miimon: None,
+        },
+        "wireguard" => Kind::Wireguard { attrs },
+        _ => bail!("Unsupported link type: {link_type}"),
+    };
+
+    Netlink::new()?.link_add(&link)
 }
 
 pub fn create_veth_pair(host_if_name: &str, peer_if_name: &str) -> Result<()> {
-    let out = run_command!(
-        "ip",
-        "link",
-        "add",
-        host_if_name,
-        "type",
-        "veth",
-        "peer",
-        "name",
-        peer_if_name
-    );
-
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    let link = Kind::Veth {
+        attrs: LinkAttrs {
+            name: host_if_name.to_string(),
+            ..Default::default()
+        },
+        peer_name: peer_if_name.to_string(),
+        peer_hw_addr: None,
+        peer_ns: None,
+        peer_index: None,
+    };
+
+    Netlink::new()?.link_add(&link)
 }
 
 pub fn set_up(if_name: &str) -> Result<()> {
-    let out = run_command!("ip", "link", "set", if_name, "up");
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(if_name)?;
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    netlink.link_setup(&link)
 }
 
 pub fn set_master(host_if_name: &str, bridge_if_name: &str) -> Result<()> {
-    let out = run_command!("ip", "link", "set", host_if_name, "master", bridge_if_name);
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(host_if_name)?;
+    let bridge = netlink.link_get_by_name(bridge_if_name)?;
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    netlink.link_set_master(&link, bridge.attrs().index)
 }
 
 pub fn set_netns(peer_if_name: &str, netns: &str) -> Result<()> {
-    let out = run_command!("ip", "link", "set", peer_if_name, "netns", netns);
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(peer_if_name)?;
+    let netns_file = File::open(format!("{NETNS_RUN_DIR}/{netns}"))?;
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    netlink.link_set_ns_fd(&link, netns_file.as_raw_fd())
 }
 
 pub fn set_link_name(peer_if_name: &str, cont_if_name: &str) -> Result<()> {
-    let out = run_command!("ip", "link", "set", peer_if_name, "name", cont_if_name);
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(peer_if_name)?;
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    netlink.link_set_name(&link, cont_if_name)
 }
 
-pub fn add_addr(cont_ip: Ipv4Addr, subnet_mask_size: &str, if_name: &str) -> Result<()> {
-    let ip = format!("{cont_ip}/{subnet_mask_size}");
-    let out = run_command!("ip", "addr", "add", &ip, "dev", if_name);
+pub fn add_addr(cont_ip: IpAddr, subnet_mask_size: &str, if_name: &str) -> Result<()> {
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(if_name)?;
+    let addr = Address {
+        ip: IpNet::new(cont_ip, subnet_mask_size.parse()?)?,
+        ..Default::default()
+    };
 
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+    netlink.addr_handle(AddrCmd::Add, &link, &addr)
 }
 
-pub fn add_default_route(gw_ip: Ipv4Addr, if_name: &str) -> Result<()> {
-    let out = run_command!(
-        "ip",
-        "route",
-        "add",
-        "default",
-        "via",
-        &gw_ip.to_string(),
-        "dev",
-        if_name
-    );
-
-    match out.status.success() {
-        true => Ok(()),
-        _ => Err(Error::msg(String::from_utf8(out.stderr).unwrap())),
-    }
+// the family of the gateway decides between 0.0.0.0/0 and ::/0
+pub fn add_default_route(gw_ip: IpAddr, if_name: &str) -> Result<()> {
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(if_name)?;
+    let route = Route {
+        oif_index: link.attrs().index,
+        gw: Some(gw_ip),
+        ..Default::default()
+    };
+
+    netlink.route_handle(RtCmd::Add, &route)
 }
 
 pub fn get_mac_addr(if_name: &str) -> Result<String> {
-    let out = run_command!("ip", "link", "show", if_name);
-
-    if let true = out.status.success() {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"/ether (?P<mac>[\w:]+) .*").unwrap();
-        }
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(if_name)?;
 
-        return Ok(RE
-            .captures(&String::from_utf8(out.stdout)?)
-            .and_then(|cap| cap.name("mac").map(|mac| mac.as_str().to_string()))
-            .unwrap());
+    // L3 devices such as tun report no IFLA_ADDRESS at all
+    if link.attrs().hw_addr().is_empty() {
+        bail!("Failed to get mac address of {if_name}");
     }
 
-    bail!("Failed to get mac address")
+    Ok(link.attrs().mac_string())
 }
 
 pub fn get_ip_addr(if_name: &str) -> Result<String> {
-    let out = run_command!("ip", "addr", "show", if_name);
-
-    if let true = out.status.success() {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"inet (?P<ip>(\b25[0-5]|\b2[0-4][0-9]|\b[01]?[0-9][0-9]?)(\.(25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)){3})/").unwrap();
-        }
-
-        return Ok(RE
-            .captures(&String::from_utf8(out.stdout)?)
-            .and_then(|cap| cap.name("ip").map(|ip| ip.as_str().to_string()))
-            .unwrap());
+    let mut netlink = Netlink::new()?;
+    let link = netlink.link_get_by_name(if_name)?;
+
+    match netlink
+        .addr_show(&link)?
+        .iter()
+        .find(|addr| addr.ip.addr().is_ipv4())
+    {
+        Some(addr) => Ok(addr.ip.addr().to_string()),
+        None => bail!("Failed to get ip address"),
     }
-
-    bail!("Failed to get ip address")
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::netlink::{get_ip_addr, get_mac_addr};
+    use crate::netlink::{add_link, get_ip_addr, get_mac_addr};
+    use netlink::netlink::Netlink;
+
+    #[test]
+    fn add_link_test() {
+        test_setup!();
+
+        add_link("br0", "bridge").unwrap();
+        add_link("host0", "veth").unwrap();
+
+        let mut netlink = Netlink::new().unwrap();
+        assert_eq!(
+            netlink.link_get_by_name("br0").unwrap().link_type(),
+            "bridge"
+        );
+
+        let veth = netlink.link_get_by_name("host0").unwrap();
+        assert_eq!(veth.link_type(), "veth");
+        assert!(veth.attrs().parent_index > 0);
+
+        assert!(add_link("macvlan0", "macvlan").is_err());
+    }
 
     #[test]
     fn get_mac_addr_test() {
@@ -163,7 +192,19 @@ mod tests {
         run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", if_name);
 
         let mac = get_mac_addr(if_name).unwrap();
-        assert!(!mac.is_empty());
+        assert_eq!(mac.len(), 17);
+        assert_eq!(mac.split(':').count(), 6);
+    }
+
+    #[test]
+    fn get_mac_addr_none_test() {
+        test_setup!();
+        let if_name = "tun0";
+
+        run_command!("ip", "tuntap", "add", "dev", if_name, "mode", "tun");
+
+        assert!(get_mac_addr(if_name).is_err());
+        assert!(get_mac_addr("missing0").is_err());
     }
 
     #[test]
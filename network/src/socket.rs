@@ -4,12 +4,12 @@ use netlink_packet_core::{
     NetlinkMessage, NetlinkPayload, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP, NLM_F_EXCL, NLM_F_REQUEST,
 };
 use netlink_packet_route::{
-    nlas::link::{Info, InfoKind, Nla},
+    nlas::link::{Info, InfoData, InfoKind, Nla, VethInfo},
     AddressMessage, LinkMessage, RouteMessage, RtnlMessage, AF_INET, AF_INET6, IFF_UP, RTN_UNICAST,
     RTPROT_STATIC, RT_SCOPE_UNIVERSE, RT_TABLE_MAIN,
 };
 use netlink_sys::{protocols::NETLINK_ROUTE, SocketAddr};
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 pub struct Socket {
     socket: netlink_sys::Socket,
@@ -20,6 +20,7 @@ pub struct Socket {
 pub struct LinkOptions {
     name: String,
     kind: InfoKind,
+    peer_name: Option<String>,
 }
 
 pub enum Route {
@@ -53,7 +54,16 @@ impl Socket {
 
     pub fn add_link(&mut self, options: LinkOptions) -> Result<()> {
         let mut msg = LinkMessage::default();
-        let link_info_nlas = vec![Info::Kind(options.kind)];
+        let mut link_info_nlas = vec![Info::Kind(options.kind.clone())];
+
+        // both ends of a veth pair are created by the same request, the peer is
+        // described by a nested link message
+        if let (InfoKind::Veth, Some(peer_name)) = (options.kind, options.peer_name) {
+            let mut peer = LinkMessage::default();
+            peer.nlas.push(Nla::IfName(peer_name));
+            link_info_nlas.push(Info::Data(InfoData::Veth(VethInfo::Peer(peer))));
+        }
+
         msg.nlas.push(Nla::Info(link_info_nlas));
         msg.nlas.push(Nla::IfName(options.name));
 
@@ -115,6 +125,72 @@ impl Socket {
         Ok(())
     }
 
+    pub fn get_addrs(&mut self, id: u32) -> Result<Vec<IpNet>> {
+        let msg = AddressMessage::default();
+
+        let result = self.request(RtnlMessage::GetAddress(msg), NLM_F_DUMP)?;
+
+        result
+            .into_iter()
+            .filter_map(|m| match m {
+                RtnlMessage::NewAddress(m) if m.header.index == id => Some(m),
+                _ => None,
+            })
+            .map(|m| {
+                // IFA_LOCAL is the interface address, IFA_ADDRESS is the peer
+                // on point-to-point links and otherwise the same as IFA_LOCAL
+                let mut local = None;
+                let mut address = None;
+
+                for nla in m.nlas {
+                    match nla {
+                        netlink_packet_route::address::Nla::Local(v) => local = Some(v),
+                        netlink_packet_route::address::Nla::Address(v) => address = Some(v),
+                        _ => {}
+                    }
+                }
+
+                let ip = match local.or(address) {
+                    Some(v) => vec_to_ip(&v)?,
+                    None => bail!("Address without IFA_LOCAL or IFA_ADDRESS"),
+                };
+
+                Ok(IpNet::new(ip, m.header.prefix_len)?)
+            })
+            .collect()
+    }
+
+    pub fn del_link(&mut self, id: u32) -> Result<()> {
+        let mut msg = LinkMessage::default();
+        msg.header.index = id;
+
+        self.request(RtnlMessage::DelLink(msg), NLM_F_ACK)?;
+        Ok(())
+    }
+
+    pub fn del_addr(&mut self, id: u32, addr: &IpNet) -> Result<()> {
+        let mut msg = AddressMessage::default();
+        msg.header.index = id;
+        msg.header.prefix_len = addr.prefix_len();
+
+        let addr_vec = match addr {
+            IpNet::V4(v4) => {
+                msg.header.family = AF_INET as u8;
+                v4.addr().octets().to_vec()
+            }
+            IpNet::V6(v6) => {
+                msg.header.family = AF_INET6 as u8;
+                v6.addr().octets().to_vec()
+            }
+        };
+
+        msg.nlas
+            .push(netlink_packet_route::address::Nla::Local(addr_vec));
+
+        self.request(RtnlMessage::DelAddress(msg), NLM_F_ACK)?;
+        Ok(())
+    }
+
     pub fn add_route(&mut self, route: &Route) -> Result<()> {
         let mut msg = RouteMessage::default();
 
@@ -216,9 +292,26 @@ impl Socket {
     }
 }
 
+fn vec_to_ip(v: &[u8]) -> Result<IpAddr> {
+    Ok(match v.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(v)?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(v)?),
+        _ => bail!("Invalid address length: {}", v.len()),
+    })
+}
+
 impl LinkOptions {
     pub fn new(name: String, kind: InfoKind) -> Self {
-        Self { name, kind }
+        Self {
+            name,
+            kind,
+            peer_name: None,
+        }
+    }
+
+    pub fn with_peer(mut self, peer_name: String) -> Self {
+        self.peer_name = Some(peer_name);
+        self
     }
 }
 
@@ -271,6 +364,21 @@ mod tests {
         assert!(out.status.success());
     }
 
+    #[test]
+    fn test_add_veth() {
+        test_setup!();
+        let mut socket = Socket::new().unwrap();
+
+        let options =
+            LinkOptions::new("veth0".to_string(), InfoKind::Veth).with_peer("veth1".to_string());
+        socket.add_link(options).unwrap();
+
+        for name in ["veth0", "veth1"] {
+            let out = run_command!("ip", "link", "show", name);
+            assert!(out.status.success());
+        }
+    }
+
     #[test]
     fn test_set_link_name() {
         test_setup!();
@@ -308,6 +416,68 @@ mod tests {
         assert!(out.contains(net));
     }
 
+    #[test]
+    fn test_get_addrs() {
+        test_setup!();
+        let mut sock = Socket::new().unwrap();
+
+        let name = "test";
+        let opt = LinkOptions::new(name.into(), InfoKind::Dummy);
+        assert!(sock.add_link(opt).is_ok());
+
+        let link = sock.get_link(name.into()).unwrap();
+
+        let nets: Vec<IpNet> = vec![
+            "10.244.0.1/24".parse().unwrap(),
+            "10.245.0.1/16".parse().unwrap(),
+        ];
+
+        for net in &nets {
+            sock.add_addr(link.header.index, net).unwrap();
+        }
+
+        let addrs = sock.get_addrs(link.header.index).unwrap();
+        assert_eq!(addrs, nets);
+    }
+
+    #[test]
+    fn test_del_link() {
+        test_setup!();
+        let mut sock = Socket::new().unwrap();
+
+        let name = "test";
+        let opt = LinkOptions::new(name.into(), InfoKind::Dummy);
+        assert!(sock.add_link(opt).is_ok());
+
+        let link = sock.get_link(name.into()).unwrap();
+
+        sock.del_link(link.header.index).unwrap();
+
+        let out = run_command!("ip", "link", "show", name);
+        assert!(!out.status.success());
+    }
+
+    #[test]
+    fn test_del_addr() {
+        test_setup!();
+        let mut sock = Socket::new().unwrap();
+
+        let name = "test";
+        let opt = LinkOptions::new(name.into(), InfoKind::Dummy);
+        assert!(sock.add_link(opt).is_ok());
+
+        let link = sock.get_link(name.into()).unwrap();
+
+        let net = "10.244.0.1/24";
+        sock.add_addr(link.header.index, &net.parse().unwrap())
+            .unwrap();
+        sock.del_addr(link.header.index, &net.parse().unwrap())
+            .unwrap();
+
+        let out = String::from_utf8(run_command!("ip", "addr", "show", name).stdout).unwrap();
+        assert!(!out.contains(net));
+    }
+
     #[test]
     fn test_add_route() {
         test_setup!();
@@ -1,7 +1,9 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::Serialize;
+use serde_json::Value;
+use std::net::Ipv4Addr;
 
-use crate::connector::veth::setup_veth;
+use crate::{connector::veth::setup_veth, Config, Dns};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,14 +11,25 @@ struct Response<'a> {
     cni_version: &'a str,
     interfaces: Vec<Interface<'a>>,
     ips: Vec<Ip<'a>>,
+    routes: Vec<Route<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dns: Option<&'a Dns>,
 }
 
 impl<'a> Response<'a> {
-    pub fn new(interfaces: Vec<Interface<'a>>, ips: Vec<Ip<'a>>) -> Self {
+    pub fn new(
+        cni_version: &'a str,
+        interfaces: Vec<Interface<'a>>,
+        ips: Vec<Ip<'a>>,
+        routes: Vec<Route<'a>>,
+        dns: Option<&'a Dns>,
+    ) -> Self {
         Self {
-            cni_version: "0.3.0",
+            cni_version,
             interfaces,
             ips,
+            routes,
+            dns,
         }
     }
 }
@@ -45,7 +58,10 @@ struct Ip<'a> {
 impl<'a> Ip<'a> {
     pub fn new(address: &'a str, gateway: &'a str) -> Self {
         Self {
-            version: "4",
+            version: match gateway.contains(':') {
+                true => "6",
+                false => "4",
+            },
             address,
             gateway,
             interface: 0,
@@ -53,44 +69,217 @@ impl<'a> Ip<'a> {
     }
 }
 
-pub fn add(cni_if_name: &str, container_id: &str, subnet: &str, netns: &str) -> Result<String> {
-    let br_if_name = "cni0";
+#[derive(Debug, Serialize)]
+struct Route<'a> {
+    dst: &'a str,
+    gw: &'a str,
+}
 
-    let (mac, address, gateway) = setup_veth(br_if_name, cni_if_name, container_id, subnet, netns)?;
+impl<'a> Route<'a> {
+    pub fn new(dst: &'a str, gw: &'a str) -> Self {
+        Self { dst, gw }
+    }
+}
+
+pub fn add(
+    config: &Config,
+    cni_if_name: &str,
+    container_id: &str,
+    netns: &str,
+    requested_ip: Option<Ipv4Addr>,
+) -> Result<String> {
+    let (mac, addresses) = setup_veth(config, cni_if_name, container_id, netns, requested_ip)?;
 
     let interface = Interface::new(cni_if_name, &mac, netns);
-    let ip = Ip::new(&address, &gateway);
-    let res = Response::new(vec![interface], vec![ip]);
+    let ips = addresses
+        .iter()
+        .map(|(address, gateway)| Ip::new(address, gateway))
+        .collect::<Vec<_>>();
+    // mirrors the default routes installed by setup_veth
+    let routes = ips
+        .iter()
+        .map(|ip| match ip.version {
+            "6" => Route::new("::/0", ip.gateway),
+            _ => Route::new("0.0.0.0/0", ip.gateway),
+        })
+        .collect();
+    let res = Response::new(
+        &config.cni_version,
+        vec![interface],
+        ips,
+        routes,
+        config.dns.as_ref(),
+    );
+
+    match &config.prev_result {
+        Some(prev) => Ok(serde_json::to_string(&chain(prev, &res)?)?),
+        None => Ok(serde_json::to_string(&res)?),
+    }
+}
+
+// when running in a chain, our interface, ips and routes are appended to the
+// result handed down by the previous plugin instead of replacing it
+fn chain(prev: &Value, res: &Response) -> Result<Value> {
+    if !prev.is_object() {
+        bail!("prevResult is not an object");
+    }
+
+    let mut merged = prev.clone();
+    let res = serde_json::to_value(res)?;
+    let offset = merged["interfaces"].as_array().map_or(0, Vec::len);
+
+    for key in ["interfaces", "ips", "routes"] {
+        if !merged[key].is_array() {
+            merged[key] = Value::Array(vec![]);
+        }
 
-    Ok(serde_json::to_string(&res)?)
+        for mut item in res[key].as_array().cloned().unwrap_or_default() {
+            // ips refer to interfaces by their position in the merged list
+            if key == "ips" {
+                item["interface"] =
+                    (offset as u64 + item["interface"].as_u64().unwrap_or(0)).into();
+            }
+
+            merged[key].as_array_mut().unwrap().push(item);
+        }
+    }
+
+    merged["cniVersion"] = res["cniVersion"].clone();
+
+    if !res["dns"].is_null() {
+        merged["dns"] = res["dns"].clone();
+    }
+
+    Ok(merged)
 }
 
 #[cfg(test)]
 mod tests {
     use network::{run_command, test_setup};
 
-    use crate::command::add::add;
+    use crate::{
+        command::add::{add, chain, Interface, Ip, Response, Route},
+        Config,
+    };
 
     #[test]
     fn add_test() {
         test_setup!();
         let cni_if_name = "eth0";
         let container_id = "123456789";
-        let subnet = "10.244.0.0/24";
         let netns = &format!("/var/run/netns/{container_id}");
+        let config = config(
+            r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24", "ipam": {"dataDir": "/tmp"}}"#,
+        );
 
         run_command!("ip", "link", "add", "cni0", "type", "bridge");
         run_command!("ip", "link", "set", "cni0", "up");
         run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", "cni0");
         run_command!("ip", "netns", "add", container_id);
 
-        let res = add(cni_if_name, container_id, subnet, netns).unwrap();
+        let res = add(&config, cni_if_name, container_id, netns, None).unwrap();
 
         println!("{res}");
-        assert!(!res.is_empty());
+        assert!(res.contains(r#""cniVersion":"0.4.0""#));
 
         let out = run_command!("ip", "link", "del", "veth12345");
 
         assert!(out.status.success(), "Failed to delete veth pair")
     }
+
+    #[test]
+    fn add_bridge_test() {
+        test_setup!();
+        let cni_if_name = "eth0";
+        let container_id = "987654321";
+        let netns = &format!("/var/run/netns/{container_id}");
+        let config = config(
+            r#"{"cniVersion": "0.4.0", "name": "comet-bridge", "subnet": "10.245.0.0/24", "bridge": "cni1", "ipam": {"dataDir": "/tmp/comet-bridge"}}"#,
+        );
+
+        run_command!("ip", "link", "add", "cni1", "type", "bridge");
+        run_command!("ip", "link", "set", "cni1", "up");
+        run_command!("ip", "addr", "add", "10.245.0.1/24", "dev", "cni1");
+        run_command!("ip", "netns", "add", container_id);
+
+        add(&config, cni_if_name, container_id, netns, None).unwrap();
+
+        let out = run_command!("ip", "link", "show", "veth98765");
+        let out = String::from_utf8_lossy(&out.stdout);
+
+        assert!(
+            out.contains("master cni1"),
+            "veth is not enslaved to cni1: {out}"
+        );
+
+        run_command!("ip", "link", "del", "veth98765");
+    }
+
+    #[test]
+    fn response_dns_test() {
+        let config = config(
+            r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24", "dns": {"nameservers": ["10.96.0.10"], "search": ["default.svc.cluster.local", "svc.cluster.local"], "options": ["ndots:5"]}}"#,
+        );
+        let res = Response::new("0.4.0", vec![], vec![], vec![], config.dns.as_ref());
+
+        let res: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&res).unwrap()).unwrap();
+
+        assert_eq!(res["dns"]["nameservers"], serde_json::json!(["10.96.0.10"]));
+        assert_eq!(
+            res["dns"]["search"],
+            serde_json::json!(["default.svc.cluster.local", "svc.cluster.local"])
+        );
+        assert_eq!(res["dns"]["options"], serde_json::json!(["ndots:5"]));
+    }
+
+    #[test]
+    fn chain_test() {
+        let config = config(
+            r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24", "prevResult": {"cniVersion": "0.4.0", "interfaces": [{"name": "bw0", "mac": "02:42:ac:11:00:01"}], "ips": [{"version": "4", "address": "10.0.0.2/24", "interface": 0}]}}"#,
+        );
+        let interface = Interface::new("eth0", "02:42:ac:11:00:02", "/var/run/netns/123456789");
+        let ip = Ip::new("10.244.0.5/24", "10.244.0.1");
+        let route = Route::new("0.0.0.0/0", "10.244.0.1");
+        let res = Response::new("0.4.0", vec![interface], vec![ip], vec![route], None);
+
+        let res = chain(config.prev_result.as_ref().unwrap(), &res).unwrap();
+
+        assert_eq!(res["interfaces"][0]["name"], "bw0");
+        assert_eq!(res["interfaces"][1]["name"], "eth0");
+        assert_eq!(res["ips"][0]["address"], "10.0.0.2/24");
+        assert_eq!(res["ips"][1]["address"], "10.244.0.5/24");
+        assert_eq!(res["ips"][1]["interface"], 1);
+        assert_eq!(res["routes"][0]["dst"], "0.0.0.0/0");
+
+        assert!(chain(
+            &serde_json::json!([]),
+            &Response::new("0.4.0", vec![], vec![], vec![], None)
+        )
+        .is_err());
+    }
+
+    fn config(json: &str) -> Config {
+        Config::from(json.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn response_test() {
+        let interface = Interface::new("eth0", "02:42:ac:11:00:02", "/var/run/netns/123456789");
+        let ip = Ip::new("10.244.0.5/24", "10.244.0.1");
+        let route = Route::new("0.0.0.0/0", "10.244.0.1");
+        let res = Response::new("0.4.0", vec![interface], vec![ip], vec![route], None);
+
+        let res: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&res).unwrap()).unwrap();
+
+        assert_eq!(res["cniVersion"], "0.4.0");
+        assert_eq!(res["ips"][0]["address"], "10.244.0.5/24");
+        assert_eq!(res["routes"][0]["dst"], "0.0.0.0/0");
+        assert_eq!(res["routes"][0]["gw"], "10.244.0.1");
+        assert!(res.get("dns").is_none());
+
+        assert_eq!(Ip::new("10.244.0.5/24", "10.244.0.1").version, "4");
+        assert_eq!(Ip::new("fd00:10:244::2/64", "fd00:10:244::1").version, "6");
+    }
 }
@@ -1,8 +1,88 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::net::IpAddr;
 
-use crate::connector::veth::release_veth;
+use crate::{connector::veth::release_veth, ipam::allocator::release_unowned_ips};
+
+pub fn del(container_id: &str, ip_store_path: &str, prev_result: Option<&Value>) -> Result<String> {
+    release_veth(container_id, ip_store_path)?;
+
+    // the result of the ADD names the addresses even when the store predates
+    // container ids and can't be searched by them
+    if let Some(prev) = prev_result {
+        release_unowned_ips(&prev_ips(prev)?, ip_store_path)?;
+    }
 
-pub fn del(if_name: &str, netns: &str) -> Result<String> {
-    release_veth(if_name, netns)?;
     Ok(String::from(""))
 }
+
+// addresses are reported as `ip/prefix`, entries that don't parse belong to
+// other plugins in the chain and are left alone
+fn prev_ips(prev: &Value) -> Result<Vec<IpAddr>> {
+    if !prev.is_object() {
+        bail!("prevResult is not an object");
+    }
+
+    Ok(prev["ips"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|ip| ip["address"].as_str())
+        .filter_map(|addr| addr.split('/').next()?.parse().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use crate::{command::del::del, ipam::allocator::allocate_ip};
+
+    #[test]
+    fn del_without_netns_test() {
+        let subnet = "10.244.0.0/24";
+        let container_id = "del_without_netns_test";
+        let ip_store_path = "/tmp/del_without_netns_test";
+
+        let _ = fs::remove_file(ip_store_path);
+
+        // the container's netns was never created, as if it had already been torn down
+        allocate_ip(subnet, container_id, None, ip_store_path).unwrap();
+
+        assert!(del(container_id, ip_store_path, None).is_ok());
+        assert!(fs::read_to_string(ip_store_path).unwrap().is_empty());
+
+        // a repeated DEL must succeed as well
+        assert!(del(container_id, ip_store_path, None).is_ok());
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn del_prev_result_test() {
+        let container_id = "del_prev_result_test";
+        let ip_store_path = "/tmp/del_prev_result_test";
+
+        // a lease from before the store was keyed by container id
+        fs::write(ip_store_path, "10.244.0.2\n10.244.0.3\n").unwrap();
+
+        let prev = json!({
+            "cniVersion": "0.4.0",
+            "interfaces": [{"name": "eth0"}],
+            "ips": [
+                {"version": "4", "address": "10.244.0.2/24", "interface": 0},
+                {"version": "4", "address": "invalid", "interface": 0},
+            ],
+        });
+
+        del(container_id, ip_store_path, Some(&prev)).unwrap();
+
+        assert_eq!(fs::read_to_string(ip_store_path).unwrap(), "10.244.0.3\n");
+
+        assert!(del(container_id, ip_store_path, Some(&json!([]))).is_err());
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+}
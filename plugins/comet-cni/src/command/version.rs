@@ -1,10 +1,12 @@
 use anyhow::Result;
 use serde_json::json;
 
+pub const SUPPORTED_VERSIONS: [&str; 5] = ["0.1.0", "0.2.0", "0.3.0", "0.3.1", "0.4.0"];
+
 pub fn version() -> Result<String> {
     Ok(json!({
         "cniVersion": "0.3.1",
-        "supportedVersions": ["0.1.0", "0.2.0", "0.3.0", "0.3.1", "0.4.0"]
+        "supportedVersions": SUPPORTED_VERSIONS
     })
     .to_string())
 }
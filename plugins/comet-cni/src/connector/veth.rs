@@ -1,14 +1,38 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use netlink::{
+    link::{Kind, LinkAttrs},
+    netlink::Netlink,
+};
 use network::netlink::{
-    add_addr, add_default_route, create_veth_pair, get_mac_addr, set_link_name, set_master,
-    set_netns, set_up,
+    add_addr, add_default_route, get_mac_addr, set_link_name, set_master, set_netns, set_up,
 };
 use nix::sched;
 use std::fs::File;
+use std::net::{IpAddr, Ipv4Addr};
 use std::os::fd::AsRawFd;
 use std::thread;
 
-use crate::ipam::{self, allocator::release_ip};
+use crate::{
+    ipam::{self, allocator::release_ip_by_container},
+    Config,
+};
+
+// both ends get the MTU at creation time, so it is already in place when they are brought up
+fn create_veth_pair(veth_if_name: &str, peer_if_name: &str, mtu: Option<u32>) -> Result<()> {
+    let mut attrs = LinkAttrs::new();
+    attrs.name = veth_if_name.to_string();
+    attrs.mtu = mtu.unwrap_or_default();
+
+    let link = Kind::Veth {
+        attrs,
+        peer_name: peer_if_name.to_string(),
+        peer_hw_addr: None,
+        peer_ns: None,
+        peer_index: None,
+    };
+
+    Netlink::new()?.link_add(&link)
+}
 
 fn create_if_name(prefix: &str, cont_id: &str) -> Result<String> {
     Ok(match cont_id.len() < 5 {
@@ -17,57 +41,105 @@ fn create_if_name(prefix: &str, cont_id: &str) -> Result<String> {
     })
 }
 
+// (container ip, gateway, prefix length) per configured family, IPv4 first
+fn allocate_ips(
+    config: &Config,
+    cont_id: &str,
+    requested_ip: Option<Ipv4Addr>,
+) -> Result<Vec<(IpAddr, IpAddr, String)>> {
+    let ip_store_path = config.ip_store_path();
+    let (_, gw_ip, cont_ip) =
+        ipam::allocator::allocate_ip(&config.subnet, cont_id, requested_ip, &ip_store_path)?;
+
+    let mut ips: Vec<(IpAddr, IpAddr, String)> = vec![(
+        cont_ip.into(),
+        gw_ip.into(),
+        config.subnet.split('/').next_back().unwrap().to_string(),
+    )];
+
+    if let Some(subnet6) = &config.subnet6 {
+        // don't leave the IPv4 address reserved for a container that never comes up
+        let (_, gw_ip, cont_ip) =
+            match ipam::allocator::allocate_ip6(subnet6, cont_id, &ip_store_path) {
+                Ok(ips) => ips,
+                Err(e) => {
+                    let _ = release_ip_by_container(cont_id, &ip_store_path);
+                    return Err(e);
+                }
+            };
+
+        ips.push((
+            cont_ip.into(),
+            gw_ip.into(),
+            subnet6.split('/').next_back().unwrap().to_string(),
+        ));
+    }
+
+    Ok(ips)
+}
+
+// returns the container's mac along with an (address, gateway) pair per
+// family, IPv4 first
 pub fn setup_veth(
-    br_if_name: &str,
+    config: &Config,
     cni_if_name: &str,
     cont_id: &str,
-    subnet: &str,
     netns_path: &str,
-) -> Result<(String, String, String)> {
+    requested_ip: Option<Ipv4Addr>,
+) -> Result<(String, Vec<(String, String)>)> {
     let veth_if_name = create_if_name("veth", cont_id)?;
     let peer_if_name = create_if_name("peer", cont_id)?;
-    let netns_name = netns_path.split('/').last().unwrap();
+    let netns_name = netns_path.split('/').next_back().unwrap();
 
-    create_veth_pair(&veth_if_name, &peer_if_name)?;
+    create_veth_pair(&veth_if_name, &peer_if_name, config.mtu)?;
     set_up(&veth_if_name)?;
-    set_master(&veth_if_name, br_if_name)?;
+    set_master(&veth_if_name, &config.bridge)?;
     set_netns(&peer_if_name, netns_name)?;
 
-    let (_, gw_ip, cont_ip) = ipam::allocator::allocate_ip(subnet, ipam::allocator::IP_STORE)?;
+    let ips = allocate_ips(config, cont_id, requested_ip)?;
 
     let netns_file = File::open(netns_path)?;
     let netns_fd = netns_file.as_raw_fd();
-    let subnet_mask_size = subnet.split('/').last().unwrap().to_string();
     let if_name = cni_if_name.to_string();
-    let address = format!("{cont_ip}/{subnet_mask_size}");
+    let addresses = ips
+        .iter()
+        .map(|(cont_ip, gw_ip, mask)| (format!("{cont_ip}/{mask}"), gw_ip.to_string()))
+        .collect();
 
     let handle = thread::spawn(move || -> Result<String> {
         sched::setns(netns_fd, sched::CloneFlags::CLONE_NEWNET)?;
         set_link_name(&peer_if_name, &if_name)?;
         set_up(&if_name)?;
-        add_addr(cont_ip, &subnet_mask_size, &if_name)?;
-        add_default_route(gw_ip, &if_name)?;
+
+        for (cont_ip, gw_ip, mask) in ips {
+            add_addr(cont_ip, &mask, &if_name)?;
+            add_default_route(gw_ip, &if_name)?;
+        }
+
         get_mac_addr(&if_name)
     });
 
-    let mac = handle.join().unwrap().unwrap();
+    let mac = handle
+        .join()
+        .map_err(|_| anyhow!("netns thread panicked"))??;
 
-    Ok((mac, address, gw_ip.to_string()))
+    Ok((mac, addresses))
 }
 
-pub fn release_veth(if_name: &str, netns: &str) -> Result<()> {
-    let netns_file = File::open(netns)?;
-    let netns_fd = netns_file.as_raw_fd();
-
-    sched::setns(netns_fd, sched::CloneFlags::CLONE_NEWNET)?;
-    release_ip(if_name, ipam::allocator::IP_STORE)
+// the veth pair goes away together with the container's netns, so only the
+// address has to be given back, which doesn't need the interface to exist anymore
+pub fn release_veth(cont_id: &str, ip_store_path: &str) -> Result<()> {
+    release_ip_by_container(cont_id, ip_store_path)
 }
 
 #[cfg(test)]
 mod tests {
     use network::{run_command, test_setup};
 
-    use crate::connector::veth::{create_if_name, setup_veth};
+    use crate::{
+        connector::veth::{allocate_ips, create_if_name, setup_veth},
+        Config,
+    };
 
     #[test]
     fn veth_test() {
@@ -75,24 +147,139 @@ mod tests {
         let br_if_name = "cni0";
         let cni_if_name = "eth0";
         let cont_id = "asdf123456789";
-        let subnet = "10.244.0.0/24";
         let netns_path = &format!("/var/run/netns/{cont_id}");
+        let config = config(
+            r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24", "ipam": {"dataDir": "/tmp"}}"#,
+        );
 
         run_command!("ip", "link", "add", br_if_name, "type", "bridge");
         run_command!("ip", "link", "set", br_if_name, "up");
         run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", br_if_name);
         run_command!("ip", "netns", "add", cont_id);
 
-        let (mac, address, gw_ip) =
-            setup_veth(br_if_name, cni_if_name, cont_id, subnet, netns_path).unwrap();
+        let (mac, ips) = setup_veth(&config, cni_if_name, cont_id, netns_path, None).unwrap();
 
         assert!(!mac.is_empty());
-        assert!(!address.is_empty());
-        assert_eq!(gw_ip, "10.244.0.1");
+        assert_eq!(ips.len(), 1);
+        assert!(!ips[0].0.is_empty());
+        assert_eq!(ips[0].1, "10.244.0.1");
 
         let veth_if_name = &create_if_name("veth", cont_id).unwrap();
         let out = run_command!("ip", "link", "del", veth_if_name);
 
         assert!(out.status.success(), "Failed to delete veth pair")
     }
+
+    #[test]
+    fn veth_mtu_test() {
+        test_setup!();
+        let br_if_name = "cni0";
+        let cni_if_name = "eth0";
+        let cont_id = "mtu123456789";
+        let netns_path = &format!("/var/run/netns/{cont_id}");
+        let config = config(
+            r#"{"cniVersion": "0.3.1", "name": "comet-mtu", "subnet": "10.244.0.0/24", "mtu": 1450, "ipam": {"dataDir": "/tmp/comet-mtu"}}"#,
+        );
+
+        run_command!("ip", "link", "add", br_if_name, "type", "bridge");
+        run_command!("ip", "link", "set", br_if_name, "up");
+        run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", br_if_name);
+        run_command!("ip", "netns", "add", cont_id);
+
+        setup_veth(&config, cni_if_name, cont_id, netns_path, None).unwrap();
+
+        let veth_if_name = &create_if_name("veth", cont_id).unwrap();
+        let out = run_command!("ip", "link", "show", veth_if_name);
+
+        assert!(String::from_utf8_lossy(&out.stdout).contains("mtu 1450"));
+
+        let out = run_command!(
+            "ip",
+            "netns",
+            "exec",
+            cont_id,
+            "ip",
+            "link",
+            "show",
+            cni_if_name
+        );
+
+        assert!(String::from_utf8_lossy(&out.stdout).contains("mtu 1450"));
+
+        run_command!("ip", "link", "del", veth_if_name);
+    }
+
+    #[test]
+    fn veth_ipv6_test() {
+        test_setup!();
+        let br_if_name = "cni0";
+        let cni_if_name = "eth0";
+        let cont_id = "ipv6123456789";
+        let netns_path = &format!("/var/run/netns/{cont_id}");
+        let config = config(
+            r#"{"cniVersion": "0.3.1", "name": "comet-ipv6", "subnet": "10.244.0.0/24", "subnet6": "fd00:10:244::/64", "ipam": {"dataDir": "/tmp/comet-ipv6"}}"#,
+        );
+        let _ = std::fs::remove_dir_all("/tmp/comet-ipv6");
+
+        run_command!("ip", "link", "add", br_if_name, "type", "bridge");
+        run_command!("ip", "link", "set", br_if_name, "up");
+        run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", br_if_name);
+        run_command!("ip", "netns", "add", cont_id);
+
+        let (_, ips) = setup_veth(&config, cni_if_name, cont_id, netns_path, None).unwrap();
+
+        assert_eq!(ips.len(), 2);
+        assert_eq!(ips[1].0, "fd00:10:244::2/64");
+        assert_eq!(ips[1].1, "fd00:10:244::1");
+
+        let out = run_command!(
+            "ip",
+            "netns",
+            "exec",
+            cont_id,
+            "ip",
+            "-6",
+            "addr",
+            "show",
+            "dev",
+            cni_if_name
+        );
+
+        assert!(String::from_utf8_lossy(&out.stdout).contains("fd00:10:244::2/64"));
+
+        let out =
+            run_command!("ip", "netns", "exec", cont_id, "ip", "-6", "route", "show", "default");
+
+        assert!(String::from_utf8_lossy(&out.stdout).contains("via fd00:10:244::1"));
+
+        run_command!(
+            "ip",
+            "link",
+            "del",
+            &create_if_name("veth", cont_id).unwrap()
+        );
+        run_command!("ip", "netns", "del", cont_id);
+    }
+
+    #[test]
+    fn allocate_ips_release_on_failure_test() {
+        let data_dir = "/tmp/comet-allocate-ips";
+        let _ = std::fs::remove_dir_all(data_dir);
+
+        // a /127 has no room for a container address after the gateway
+        let config = config(
+            r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24", "subnet6": "fd00:10:244::/127", "ipam": {"dataDir": "/tmp/comet-allocate-ips"}}"#,
+        );
+
+        assert!(allocate_ips(&config, "123456789", None).is_err());
+        assert!(std::fs::read_to_string(config.ip_store_path())
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    fn config(json: &str) -> Config {
+        Config::from(json.as_bytes()).unwrap()
+    }
 }
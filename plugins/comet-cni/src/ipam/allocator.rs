@@ -1,13 +1,18 @@
-use anyhow::{bail, Result};
-use network::{ip::nmap, netlink::get_ip_addr, run_command};
+use anyhow::{anyhow, bail, Result};
+use ipnet::{Ipv4Net, Ipv6Net};
+use network::ip::{nmap6, nmap_hosts, NMAP6_MAX_HOST_BITS};
+use nix::fcntl::{flock, FlockArg};
 use std::{
+    collections::HashSet,
     fs::{self, File},
     io::{self, BufRead, Write},
-    net::Ipv4Addr,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::fd::AsRawFd,
     path::Path,
 };
 
-pub const IP_STORE: &str = "/tmp/reserved_ips";
+pub const DEFAULT_DATA_DIR: &str = "/var/lib/cni/comet";
+pub const IP_STORE_FILE: &str = "reserved_ips";
 
 fn read_lines<P>(path: P) -> Result<io::Lines<io::BufReader<File>>>
 where
@@ -17,61 +22,205 @@ where
     Ok(io::BufReader::new(file).lines())
 }
 
-fn get_reserved_ips(path: &str) -> Result<Vec<Ipv4Addr>> {
+fn open_store(path: &str) -> Result<File> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    Ok(fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?)
+}
+
+// each line of the store is `<container id> <ip>`, older stores only hold the ip.
+// blank or corrupt lines are skipped so that they can't break every later ADD
+fn get_reserved_ips(path: &str) -> Result<Vec<IpAddr>> {
     if let Ok(lines) = read_lines(path) {
-        return Ok(lines
-            .map(|ip| ip.unwrap().parse::<Ipv4Addr>().unwrap())
-            .collect::<Vec<_>>());
+        let mut ips = Vec::new();
+
+        for line in lines {
+            if let Some(ip) = line?
+                .split_whitespace()
+                .last()
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+            {
+                ips.push(ip);
+            }
+        }
+
+        return Ok(ips);
     }
 
     bail!("Failed to get reserved IPs")
 }
 
-pub fn allocate_ip(subnet: &str, ip_store_path: &str) -> Result<(Ipv4Addr, Ipv4Addr, Ipv4Addr)> {
-    let mut file = fs::OpenOptions::new()
-        .write(true)
-        .append(true)
-        .create(true)
-        .open(ip_store_path)
-        .unwrap();
+pub fn allocate_ip(
+    subnet: &str,
+    container_id: &str,
+    requested_ip: Option<Ipv4Addr>,
+    ip_store_path: &str,
+) -> Result<(Ipv4Addr, Ipv4Addr, Ipv4Addr)> {
+    let mut file = open_store(ip_store_path)?;
+
+    // hold the lock until the file is closed so that concurrent ADDs can't pick the same IP
+    flock(file.as_raw_fd(), FlockArg::LockExclusive)?;
 
-    let mut all_ips = nmap(subnet)?;
+    let mut all_ips = nmap_hosts(subnet)?;
     let reserved_ips = get_reserved_ips(ip_store_path)?;
 
+    if let Some(ip) = requested_ip {
+        if !all_ips.contains(&ip) {
+            bail!("requested IP {ip} is not a host address in subnet {subnet}");
+        }
+    }
+
     for ip in reserved_ips {
-        all_ips.remove(&ip);
+        if let IpAddr::V4(ip) = ip {
+            all_ips.remove(&ip);
+        }
     }
 
-    let skip_ip = all_ips.pop_first().unwrap();
-    let gw_ip = all_ips.pop_first().unwrap();
-    let cont_ip = all_ips.pop_first().unwrap();
+    let skip_ip = subnet.parse::<Ipv4Net>()?.network();
+    let gw_ip = all_ips.pop_first().ok_or_else(|| exhausted(subnet))?;
+    let cont_ip = match requested_ip {
+        Some(ip) if all_ips.remove(&ip) => ip,
+        Some(ip) => bail!("requested IP {ip} is already reserved"),
+        None => all_ips.pop_first().ok_or_else(|| exhausted(subnet))?,
+    };
 
-    writeln!(file, "{}", &cont_ip.to_string()).unwrap();
+    writeln!(file, "{container_id} {cont_ip}")?;
 
     Ok((skip_ip, gw_ip, cont_ip))
 }
 
-pub fn release_ip(if_name: &str, ip_store_path: &str) -> Result<()> {
-    let ip_addr = get_ip_addr(if_name)?;
-    let opt = format!("/{ip_addr}/d");
-    let out = run_command!("sed", "-i", &opt, ip_store_path);
+pub fn allocate_ip6(
+    subnet: &str,
+    container_id: &str,
+    ip_store_path: &str,
+) -> Result<(Ipv6Addr, Ipv6Addr, Ipv6Addr)> {
+    let mut file = open_store(ip_store_path)?;
 
-    if let false = out.status.success() {
-        bail!("Failed to release ip")
-    }
+    flock(file.as_raw_fd(), FlockArg::LockExclusive)?;
+
+    let net = subnet.parse::<Ipv6Net>()?;
+    let reserved_ips = get_reserved_ips(ip_store_path)?
+        .into_iter()
+        .filter_map(|ip| match ip {
+            IpAddr::V6(ip) => Some(ip),
+            IpAddr::V4(_) => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let (skip_ip, gw_ip, cont_ip) = if 128 - net.prefix_len() <= NMAP6_MAX_HOST_BITS {
+        let mut all_ips = nmap6(subnet)?;
+
+        for ip in &reserved_ips {
+            all_ips.remove(ip);
+        }
+
+        let skip_ip = all_ips.pop_first().ok_or_else(|| exhausted(subnet))?;
+        let gw_ip = all_ips.pop_first().ok_or_else(|| exhausted(subnet))?;
+        let cont_ip = all_ips.pop_first().ok_or_else(|| exhausted(subnet))?;
+
+        (skip_ip, gw_ip, cont_ip)
+    } else {
+        let base = u128::from(net.network());
+        let cont_ip = next_ip6(&net, &reserved_ips, &format!("{ip_store_path}.v6"))?;
+
+        (Ipv6Addr::from(base), Ipv6Addr::from(base + 1), cont_ip)
+    };
+
+    writeln!(file, "{container_id} {cont_ip}")?;
+
+    Ok((skip_ip, gw_ip, cont_ip))
+}
+
+// subnets such as a /64 can't be enumerated, so hand out addresses sequentially
+// starting after the gateway and persist the next host offset to use
+fn next_ip6(
+    net: &Ipv6Net,
+    reserved_ips: &HashSet<Ipv6Addr>,
+    counter_path: &str,
+) -> Result<Ipv6Addr> {
+    let base = u128::from(net.network());
+    let size = 1u128
+        .checked_shl((128 - net.prefix_len()) as u32)
+        .unwrap_or(u128::MAX);
+
+    let mut offset = match fs::read_to_string(counter_path) {
+        Ok(counter) => counter.trim().parse::<u128>()?,
+        Err(_) => 2,
+    };
+
+    let ip = loop {
+        if offset >= size {
+            return Err(exhausted(&net.to_string()));
+        }
+
+        let ip = Ipv6Addr::from(base + offset);
+        offset += 1;
+
+        if !reserved_ips.contains(&ip) {
+            break ip;
+        }
+    };
+
+    fs::write(counter_path, offset.to_string())?;
+
+    Ok(ip)
+}
+
+fn exhausted(subnet: &str) -> anyhow::Error {
+    anyhow!("no free addresses in subnet {subnet}")
+}
+
+pub fn release_ip_by_container(container_id: &str, ip_store_path: &str) -> Result<()> {
+    retain_leases(ip_store_path, |line| {
+        line.split_whitespace().next() != Some(container_id)
+    })
+}
+
+// lines written before the store was keyed by container id only hold the ip,
+// so the only way to give them back is by address
+pub fn release_unowned_ips(ips: &[IpAddr], ip_store_path: &str) -> Result<()> {
+    retain_leases(ip_store_path, |line| {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [ip] => !ip.parse::<IpAddr>().is_ok_and(|ip| ips.contains(&ip)),
+            _ => true,
+        }
+    })
+}
+
+fn retain_leases<F>(ip_store_path: &str, keep: F) -> Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    let file = open_store(ip_store_path)?;
+
+    flock(file.as_raw_fd(), FlockArg::LockExclusive)?;
+
+    // read the whole store before rewriting it, a partial read would drop
+    // every lease after the unreadable line
+    let lines = read_lines(ip_store_path)?
+        .collect::<io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|line| keep(line))
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+
+    fs::write(ip_store_path, lines)?;
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{collections::HashSet, fs, thread};
 
-    use network::{run_command, test_setup};
+    use crate::ipam::allocator::{release_ip_by_container, release_unowned_ips};
 
-    use crate::ipam::allocator::release_ip;
-
-    use super::{allocate_ip, get_reserved_ips};
+    use super::{allocate_ip, allocate_ip6, get_reserved_ips};
 
     #[test]
     fn get_reserved_ips_test() {
@@ -86,6 +235,24 @@ mod tests {
         fs::remove_file(ip_store_path).unwrap();
     }
 
+    #[test]
+    fn get_reserved_ips_corrupt_test() {
+        let ip_store_path = "/tmp/reserved_ips_corrupt_test";
+
+        fs::write(
+            ip_store_path,
+            "123456789 10.244.0.2\n\n123456789 garbage\n10.244.0.3\n",
+        )
+        .unwrap();
+
+        let reserved_ips = get_reserved_ips(ip_store_path).unwrap();
+
+        assert_eq!(reserved_ips.len(), 2);
+        assert_eq!(reserved_ips[1].to_string(), "10.244.0.3");
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
     #[test]
     fn allocate_ip_test() {
         let subnet = "10.244.0.0/24";
@@ -93,7 +260,8 @@ mod tests {
 
         fs::write(ip_store_path, "10.244.0.2\n10.244.0.3\n10.244.0.4\n").unwrap();
 
-        let (skip_ip, gw_ip, cont_ip) = allocate_ip(subnet, ip_store_path).unwrap();
+        let (skip_ip, gw_ip, cont_ip) =
+            allocate_ip(subnet, "123456789", None, ip_store_path).unwrap();
 
         assert_eq!(skip_ip.to_string(), "10.244.0.0");
         assert_eq!(gw_ip.to_string(), "10.244.0.1");
@@ -108,22 +276,235 @@ mod tests {
     }
 
     #[test]
-    fn release_ip_test() {
-        test_setup!();
-        let if_name = "cni0";
-        let ip_store_path = "/tmp/release_ip_test";
+    fn allocate_ip_skips_broadcast_test() {
+        let subnet = "10.244.0.0/24";
+        let ip_store_path = "/tmp/allocate_ip_skips_broadcast_test";
 
-        fs::write(ip_store_path, "10.244.0.0\n10.244.0.1\n10.244.0.2\n").unwrap();
+        let reserved = (2..=253)
+            .map(|i| format!("10.244.0.{i}\n"))
+            .collect::<String>();
+        fs::write(ip_store_path, reserved).unwrap();
 
-        run_command!("ip", "link", "add", if_name, "type", "bridge");
-        run_command!("ip", "link", "set", if_name, "up");
-        run_command!("ip", "addr", "add", "10.244.0.1/24", "dev", if_name);
+        let (skip_ip, gw_ip, cont_ip) =
+            allocate_ip(subnet, "123456789", None, ip_store_path).unwrap();
 
-        release_ip(if_name, ip_store_path).unwrap();
+        assert_eq!(skip_ip.to_string(), "10.244.0.0");
+        assert_eq!(gw_ip.to_string(), "10.244.0.1");
+        assert_eq!(cont_ip.to_string(), "10.244.0.254");
+
+        let reserved_ips = get_reserved_ips(ip_store_path).unwrap();
+
+        assert!(!reserved_ips.contains(&"10.244.0.0".parse().unwrap()));
+        assert!(!reserved_ips.contains(&"10.244.0.255".parse().unwrap()));
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_requested_ip_test() {
+        let subnet = "10.244.0.0/24";
+        let ip_store_path = "/tmp/allocate_requested_ip_test";
+
+        fs::write(ip_store_path, "10.244.0.2\n").unwrap();
+
+        let requested_ip = "10.244.0.50".parse().unwrap();
+        let (_, gw_ip, cont_ip) =
+            allocate_ip(subnet, "123456789", Some(requested_ip), ip_store_path).unwrap();
+
+        assert_eq!(gw_ip.to_string(), "10.244.0.1");
+        assert_eq!(cont_ip, requested_ip);
+
+        let reserved_ips = get_reserved_ips(ip_store_path).unwrap();
+
+        assert_eq!(reserved_ips.last().unwrap().to_string(), "10.244.0.50");
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_requested_ip_reserved_test() {
+        let subnet = "10.244.0.0/24";
+        let ip_store_path = "/tmp/allocate_requested_ip_reserved_test";
+
+        fs::write(ip_store_path, "other 10.244.0.50\n").unwrap();
+
+        let err = allocate_ip(
+            subnet,
+            "123456789",
+            Some("10.244.0.50".parse().unwrap()),
+            ip_store_path,
+        )
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            err.to_string(),
+            "requested IP 10.244.0.50 is already reserved"
+        );
+
+        let err = allocate_ip(
+            subnet,
+            "123456789",
+            Some("10.245.0.50".parse().unwrap()),
+            ip_store_path,
+        )
+        .err()
+        .unwrap();
+
+        assert!(err.to_string().contains("not a host address"));
+        assert_eq!(get_reserved_ips(ip_store_path).unwrap().len(), 1);
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_ip_exhausted_test() {
+        let subnet = "10.244.0.0/29";
+        let ip_store_path = "/tmp/allocate_ip_exhausted_test";
+
+        let reserved = (2..=6)
+            .map(|i| format!("10.244.0.{i}\n"))
+            .collect::<String>();
+        fs::write(ip_store_path, reserved).unwrap();
+
+        let err = allocate_ip(subnet, "123456789", None, ip_store_path)
+            .err()
+            .unwrap();
+
+        assert_eq!(err.to_string(), "no free addresses in subnet 10.244.0.0/29");
+        assert_eq!(get_reserved_ips(ip_store_path).unwrap().len(), 5);
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_ip6_test() {
+        let subnet = "fd00:10:244::/120";
+        let ip_store_path = "/tmp/allocate_ip6_test";
+
+        fs::write(ip_store_path, "10.244.0.2\nfd00:10:244::2\n").unwrap();
+
+        let (skip_ip, gw_ip, cont_ip) = allocate_ip6(subnet, "123456789", ip_store_path).unwrap();
+
+        assert_eq!(skip_ip.to_string(), "fd00:10:244::");
+        assert_eq!(gw_ip.to_string(), "fd00:10:244::1");
+        assert_eq!(cont_ip.to_string(), "fd00:10:244::3");
+
+        let reserved_ips = get_reserved_ips(ip_store_path).unwrap();
+
+        assert_eq!(reserved_ips.len(), 3);
+        assert_eq!(reserved_ips.last().unwrap().to_string(), "fd00:10:244::3");
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_ip6_counter_test() {
+        let subnet = "fd00:10:244::/64";
+        let ip_store_path = "/tmp/allocate_ip6_counter_test";
+        let counter_path = &format!("{ip_store_path}.v6");
+
+        let _ = fs::remove_file(counter_path);
+        fs::write(ip_store_path, "fd00:10:244::3\n").unwrap();
+
+        let (_, gw_ip, cont_ip) = allocate_ip6(subnet, "123456789", ip_store_path).unwrap();
+
+        assert_eq!(gw_ip.to_string(), "fd00:10:244::1");
+        assert_eq!(cont_ip.to_string(), "fd00:10:244::2");
+
+        // ::3 is already reserved, so the counter skips over it
+        let (_, _, cont_ip) = allocate_ip6(subnet, "123456789", ip_store_path).unwrap();
+
+        assert_eq!(cont_ip.to_string(), "fd00:10:244::4");
+        assert_eq!(fs::read_to_string(counter_path).unwrap(), "5");
+
+        fs::remove_file(ip_store_path).unwrap();
+        fs::remove_file(counter_path).unwrap();
+    }
+
+    #[test]
+    fn allocate_ip_concurrent_test() {
+        let subnet = "10.244.0.0/24";
+        let ip_store_path = "/tmp/allocate_ip_concurrent_test";
+
+        let _ = fs::remove_file(ip_store_path);
+
+        let handles = (0..8)
+            .map(|i| {
+                thread::spawn(move || {
+                    allocate_ip(subnet, &format!("container{i}"), None, ip_store_path)
+                        .unwrap()
+                        .2
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let ips = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<HashSet<_>>();
+
+        assert_eq!(ips.len(), 8);
+        assert_eq!(get_reserved_ips(ip_store_path).unwrap().len(), 8);
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn release_ip_by_container_test() {
+        let subnet = "10.244.0.0/24";
+        let ip_store_path = "/tmp/release_ip_by_container_test";
+
+        fs::write(ip_store_path, "10.244.0.2\n").unwrap();
+
+        let (_, _, first_ip) = allocate_ip(subnet, "first", None, ip_store_path).unwrap();
+        let (_, _, second_ip) = allocate_ip(subnet, "second", None, ip_store_path).unwrap();
+
+        // no interface is involved, so this works even if the container is already gone
+        release_ip_by_container("first", ip_store_path).unwrap();
 
         let reserved_ips = get_reserved_ips(ip_store_path).unwrap();
 
-        println!("{reserved_ips:?}");
         assert_eq!(reserved_ips.len(), 2);
+        assert!(!reserved_ips.contains(&first_ip.into()));
+        assert!(reserved_ips.contains(&second_ip.into()));
+
+        // releasing an unknown container is a no-op
+        release_ip_by_container("unknown", ip_store_path).unwrap();
+        assert_eq!(get_reserved_ips(ip_store_path).unwrap().len(), 2);
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn release_ip_by_container_unreadable_test() {
+        let ip_store_path = "/tmp/release_ip_by_container_unreadable_test";
+        let store = b"first 10.244.0.2\n\xff\xfe\nsecond 10.244.0.3\n";
+
+        fs::write(ip_store_path, store).unwrap();
+
+        // the store is left alone instead of being rewritten from a partial read
+        assert!(release_ip_by_container("first", ip_store_path).is_err());
+        assert_eq!(fs::read(ip_store_path).unwrap(), store);
+
+        fs::remove_file(ip_store_path).unwrap();
+    }
+
+    #[test]
+    fn release_unowned_ips_test() {
+        let ip_store_path = "/tmp/release_unowned_ips_test";
+
+        fs::write(ip_store_path, "10.244.0.2\n10.244.0.3\nother 10.244.0.4\n").unwrap();
+
+        let ips = ["10.244.0.2", "10.244.0.4"].map(|ip| ip.parse().unwrap());
+        release_unowned_ips(&ips, ip_store_path).unwrap();
+
+        // a lease that names its container is never released by address
+        assert_eq!(
+            fs::read_to_string(ip_store_path).unwrap(),
+            "10.244.0.3\nother 10.244.0.4\n"
+        );
+
+        fs::remove_file(ip_store_path).unwrap();
     }
 }
@@ -1,26 +1,42 @@
 use std::{
-    fs::{File, OpenOptions},
-    io::Write,
-    path::Path,
+    env,
+    fs::OpenOptions,
+    io::{self, Write},
 };
 
-const LOG_FILE_NAME: &str = "/var/log/comet-cni.log";
+const LOG_FILE_ENV: &str = "COMET_LOG_FILE";
+const DEFAULT_LOG_FILE: &str = "/var/log/comet-cni.log";
 
+// logging is best-effort, a log file that can't be written must never fail
+// the CNI operation itself
 pub fn log(msg: &str) {
-    let mut file = log_file();
+    let path = env::var(LOG_FILE_ENV).unwrap_or_else(|_| DEFAULT_LOG_FILE.to_string());
+    let _ = log_to(&path, msg);
+}
+
+fn log_to(path: &str, msg: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().append(true).create(true).open(path)?;
 
-    file.write_all(msg.as_bytes())
-        .expect("Failed to write to log file");
-    file.flush().expect("Failed to flush to log file");
+    file.write_all(msg.as_bytes())?;
+    file.flush()
 }
 
-fn log_file() -> File {
-    return if let true = Path::new(LOG_FILE_NAME).exists() {
-        OpenOptions::new()
-            .append(true)
-            .open(LOG_FILE_NAME)
-            .expect("Failed to open log file")
-    } else {
-        File::create(LOG_FILE_NAME).expect("Failed to create log file")
-    };
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::log::logging::log_to;
+
+    #[test]
+    fn log_test() {
+        let path = "/tmp/comet-cni-log-test.log";
+        let _ = fs::remove_file(path);
+
+        log_to(path, "hello\n").unwrap();
+
+        assert_eq!(fs::read_to_string(path).unwrap(), "hello\n");
+        assert!(log_to("/nonexistent/dir/comet-cni.log", "hello\n").is_err());
+
+        fs::remove_file(path).unwrap();
+    }
 }
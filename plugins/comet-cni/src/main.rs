@@ -3,20 +3,52 @@ pub mod connector;
 pub mod ipam;
 pub mod log;
 
-use anyhow::{Error, Result};
-use serde::Deserialize;
+use anyhow::{anyhow, bail, Error, Result};
+use ipnet::{IpNet, Ipv6Net};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::io::BufRead;
-use std::{env, io};
+use std::{
+    collections::HashMap,
+    env, fmt, io,
+    net::{IpAddr, Ipv4Addr},
+    process,
+};
 
-use crate::log::logging::log;
+use crate::{
+    command::version::SUPPORTED_VERSIONS,
+    ipam::allocator::{DEFAULT_DATA_DIR, IP_STORE_FILE},
+    log::logging::log,
+};
 
+// VERSION is called without a container, so everything but CNI_COMMAND and
+// the network config may be missing depending on the command
 #[derive(Debug)]
 struct Opts {
     command: String,
-    netns: String,
-    container_id: String,
-    if_name: String,
-    config: Config,
+    netns: Option<String>,
+    container_id: Option<String>,
+    if_name: Option<String>,
+    args: HashMap<String, String>,
+    pod: Option<Pod>,
+    config: Option<Config>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Pod {
+    name: String,
+    namespace: String,
+    infra_container_id: Option<String>,
+}
+
+impl Pod {
+    fn from(args: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            name: args.get("K8S_POD_NAME")?.clone(),
+            namespace: args.get("K8S_POD_NAMESPACE")?.clone(),
+            infra_container_id: args.get("K8S_POD_INFRA_CONTAINER_ID").cloned(),
+        })
+    }
 }
 
 impl Opts {
@@ -24,36 +56,140 @@ impl Opts {
     where
         R: BufRead,
     {
+        Self::from_vars(reader, |key| env::var(key).ok())
+    }
+
+    fn from_vars<R, F>(reader: R, var: F) -> Result<Self>
+    where
+        R: BufRead,
+        F: Fn(&str) -> Option<String>,
+    {
+        let command = var("CNI_COMMAND").ok_or(CniError::MissingVar("CNI_COMMAND"))?;
+        let args = parse_args(&var("CNI_ARGS").unwrap_or_default());
+
         Ok(Self {
-            command: env::var("CNI_COMMAND").expect("Failed to get CNI_COMMAND"),
-            netns: env::var("CNI_NETNS").expect("Failed to get CNI_NETNS"),
-            container_id: env::var("CNI_CONTAINERID").expect("Failed to get CNI_CONTAINERID"),
-            if_name: env::var("CNI_IFNAME").expect("Failed to get CNI_IFNAME"),
-            config: Config::from(reader)?,
+            config: match &command[..] {
+                "VERSION" => None,
+                _ => Some(Config::from(reader)?),
+            },
+            command,
+            netns: var("CNI_NETNS"),
+            container_id: var("CNI_CONTAINERID"),
+            if_name: var("CNI_IFNAME"),
+            pod: Pod::from(&args),
+            args,
         })
     }
 
+    fn log(&self, msg: &str) {
+        match &self.pod {
+            Some(pod) => log(&format!("[{}/{}] {}", pod.namespace, pod.name, msg)),
+            None => log(msg),
+        }
+    }
+
     fn handle(self) -> Result<String> {
         match &self.command[..] {
             "ADD" => Ok(command::add::add(
-                &self.if_name,
-                &self.container_id,
-                &self.config.subnet,
-                &self.netns,
+                self.config()?,
+                required(&self.if_name, "CNI_IFNAME")?,
+                required(&self.container_id, "CNI_CONTAINERID")?,
+                required(&self.netns, "CNI_NETNS")?,
+                requested_ip(&self.args)?,
             )?),
-            "DEL" => command::del::del(&self.if_name, &self.netns),
-            "GET" => Ok(String::from("GET not supported")),
+            // the netns may already be gone by the time DEL is called
+            "DEL" => command::del::del(
+                required(&self.container_id, "CNI_CONTAINERID")?,
+                &self.config()?.ip_store_path(),
+                self.config()?.prev_result.as_ref(),
+            ),
             "VERSION" => command::version::version(),
-            _ => Err(Error::msg(format!("Unknown CNI command: {}", self.command))),
+            // GET was dropped from the spec after 0.4.0 and was never implemented
+            _ => Err(CniError::UnsupportedCommand(self.command.clone()).into()),
         }
     }
+
+    fn cni_version(&self) -> Option<&str> {
+        self.config
+            .as_ref()
+            .map(|config| config.cni_version.as_str())
+    }
+
+    fn config(&self) -> Result<&Config> {
+        self.config
+            .as_ref()
+            .ok_or_else(|| anyhow!("{} needs a network config", self.command))
+    }
+}
+
+fn required<'a>(value: &'a Option<String>, name: &'static str) -> Result<&'a str> {
+    Ok(value.as_deref().ok_or(CniError::MissingVar(name))?)
+}
+
+// CNI_ARGS is a list of `KEY=VALUE` pairs separated by semicolons
+fn parse_args(args: &str) -> HashMap<String, String> {
+    args.split(';')
+        .filter_map(|arg| arg.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+// only an IPv4 address can be pinned
+fn requested_ip(args: &HashMap<String, String>) -> Result<Option<Ipv4Addr>> {
+    match args.get("IP").map(|ip| ip.parse::<IpAddr>()).transpose()? {
+        Some(IpAddr::V4(ip)) => Ok(Some(ip)),
+        Some(IpAddr::V6(ip)) => {
+            bail!("requested IP {ip} is IPv6, only IPv4 addresses can be requested")
+        }
+        None => Ok(None),
+    }
 }
 
 #[derive(Debug, Deserialize)]
-struct Config {
-    // name: String,
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    cni_version: String,
+    name: String,
     // network: String,
     subnet: String,
+    // pods additionally get an address out of this subnet when set
+    #[serde(default)]
+    subnet6: Option<String>,
+    #[serde(default = "default_bridge")]
+    bridge: String,
+    #[serde(default)]
+    mtu: Option<u32>,
+    #[serde(default)]
+    ipam: IpamConfig,
+    #[serde(default)]
+    dns: Option<Dns>,
+    // the result of the previous plugin when running in a chain
+    #[serde(default)]
+    prev_result: Option<serde_json::Value>,
+}
+
+const DEFAULT_BRIDGE: &str = "cni0";
+
+fn default_bridge() -> String {
+    DEFAULT_BRIDGE.to_string()
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IpamConfig {
+    data_dir: Option<String>,
+}
+
+// https://www.cni.dev/docs/spec/#dns, passed through to the ADD result as is
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Dns {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nameservers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    search: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    options: Vec<String>,
 }
 
 impl Config {
@@ -64,31 +200,132 @@ impl Config {
         let mut buffer = String::new();
         reader.read_to_string(&mut buffer)?;
         let stdin_json: Self = serde_json::from_str(buffer.as_str())?;
+
+        if !SUPPORTED_VERSIONS.contains(&stdin_json.cni_version.as_str()) {
+            bail!("unsupported CNI version: {}", stdin_json.cni_version);
+        }
+
+        if stdin_json.subnet.parse::<IpNet>().is_err() {
+            bail!("invalid subnet: {}", stdin_json.subnet);
+        }
+
+        if let Some(subnet6) = &stdin_json.subnet6 {
+            if subnet6.parse::<Ipv6Net>().is_err() {
+                bail!("invalid subnet6: {}", subnet6);
+            }
+        }
+
         Ok(stdin_json)
     }
+
+    fn ip_store_path(&self) -> String {
+        let data_dir = match &self.ipam.data_dir {
+            Some(data_dir) => data_dir.clone(),
+            None => format!("{}/{}", DEFAULT_DATA_DIR, self.name),
+        };
+
+        format!("{data_dir}/{IP_STORE_FILE}")
+    }
 }
 
-fn main() {
-    let opts = Opts::new(io::stdin().lock()).unwrap();
+// https://www.cni.dev/docs/spec/#error
+const ERR_CODE_INVALID_ENV: u32 = 4;
+const ERR_CODE_GENERIC: u32 = 7;
 
-    log(&format!("CNI command: {}\n", opts.command));
-    log(&format!("stdin: {opts:?}\n"));
+// errors which map to a more specific code than ERR_CODE_GENERIC
+#[derive(Debug)]
+enum CniError {
+    MissingVar(&'static str),
+    UnsupportedCommand(String),
+}
 
-    println!("{}", opts.handle().unwrap());
+impl CniError {
+    fn code(&self) -> u32 {
+        match self {
+            CniError::MissingVar(_) | CniError::UnsupportedCommand(_) => ERR_CODE_INVALID_ENV,
+        }
+    }
+}
+
+impl fmt::Display for CniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CniError::MissingVar(name) => write!(f, "{name} is not set"),
+            CniError::UnsupportedCommand(command) => {
+                write!(f, "unsupported CNI command: {command}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CniError {}
+
+// used for errors raised before the network config could be read
+const DEFAULT_ERR_VERSION: &str = "0.3.1";
+
+fn error_response(err: &Error, cni_version: Option<&str>) -> String {
+    json!({
+        "cniVersion": cni_version.unwrap_or(DEFAULT_ERR_VERSION),
+        "code": err.downcast_ref::<CniError>().map_or(ERR_CODE_GENERIC, CniError::code),
+        "msg": err.to_string(),
+        "details": format!("{err:#}"),
+    })
+    .to_string()
+}
+
+fn run(opts: Opts) -> Result<String> {
+    opts.log(&format!("CNI command: {}\n", opts.command));
+    opts.log(&format!("stdin: {opts:?}\n"));
+
+    opts.handle()
+}
+
+fn main() {
+    let opts = Opts::new(io::stdin().lock());
+    let cni_version = opts
+        .as_ref()
+        .ok()
+        .and_then(|opts| opts.cni_version())
+        .map(String::from);
+
+    match opts.and_then(run) {
+        Ok(res) => println!("{res}"),
+        Err(err) => {
+            log(&format!("error: {err:?}\n"));
+            println!("{}", error_response(&err, cni_version.as_deref()));
+            process::exit(1);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::env;
+    use std::{collections::HashMap, fs, path::Path};
+
+    use anyhow::anyhow;
 
-    use crate::Opts;
+    use crate::{
+        error_response, ipam::allocator::allocate_ip, parse_args, requested_ip, run, Config, Opts,
+        Pod,
+    };
+
+    // the variables a runtime sets for ADD, passed to Opts::from_vars rather
+    // than set on the process environment which is shared by parallel tests
+    fn add_vars() -> HashMap<String, String> {
+        [
+            ("CNI_COMMAND", "ADD"),
+            ("CNI_NETNS", "/var/run/netns/123456789"),
+            ("CNI_CONTAINERID", "123456789"),
+            ("CNI_IFNAME", "eth0"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
 
     #[test]
     fn opts_test() {
-        env::set_var("CNI_COMMAND", "ADD");
-        env::set_var("CNI_NETNS", "/var/run/netns/123456789");
-        env::set_var("CNI_CONTAINERID", "123456789");
-        env::set_var("CNI_IFNAME", "eth0");
+        let vars = add_vars();
 
         let input = r#"
         {
@@ -101,14 +338,286 @@ mod tests {
         "#
         .as_bytes();
 
-        let opts = Opts::new(input).unwrap();
+        let opts = Opts::from_vars(input, |key| vars.get(key).cloned()).unwrap();
 
         assert_eq!(opts.command, "ADD");
-        assert_eq!(opts.netns, "/var/run/netns/123456789");
-        assert_eq!(opts.container_id, "123456789");
-        assert_eq!(opts.if_name, "eth0");
-        // assert_eq!(opts.config.name, "comet");
-        // assert_eq!(opts.config.network, "10.244.0.0/16");
-        assert_eq!(opts.config.subnet, "10.244.0.0/24");
+        assert_eq!(opts.netns.as_deref(), Some("/var/run/netns/123456789"));
+        assert_eq!(opts.container_id.as_deref(), Some("123456789"));
+        assert_eq!(opts.if_name.as_deref(), Some("eth0"));
+
+        let config = opts.config.unwrap();
+
+        assert_eq!(config.cni_version, "0.3.1");
+        assert_eq!(config.name, "comet");
+        // assert_eq!(config.network, "10.244.0.0/16");
+        assert_eq!(config.subnet, "10.244.0.0/24");
+        assert_eq!(
+            config.ip_store_path(),
+            "/var/lib/cni/comet/comet/reserved_ips"
+        );
+    }
+
+    #[test]
+    fn opts_version_test() {
+        // the runtime only passes the CNI version on stdin for VERSION
+        let input = r#"{"cniVersion": "0.4.0"}"#.as_bytes();
+        let opts = Opts::from_vars(input, |key| match key {
+            "CNI_COMMAND" => Some("VERSION".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert!(opts.netns.is_none());
+        assert!(opts.config.is_none());
+
+        let res: serde_json::Value = serde_json::from_str(&opts.handle().unwrap()).unwrap();
+
+        assert_eq!(res["supportedVersions"][4], "0.4.0");
+    }
+
+    #[test]
+    fn opts_missing_var_test() {
+        let input = r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24"}"#;
+
+        let err = Opts::from_vars(input.as_bytes(), |_| None).err().unwrap();
+        let res: serde_json::Value = serde_json::from_str(&error_response(&err, None)).unwrap();
+
+        assert_eq!(res["code"], 4);
+        assert_eq!(res["msg"], "CNI_COMMAND is not set");
+
+        let opts = Opts::from_vars(input.as_bytes(), |key| match key {
+            "CNI_COMMAND" => Some("ADD".to_string()),
+            "CNI_CONTAINERID" => Some("123456789".to_string()),
+            "CNI_IFNAME" => Some("eth0".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        let err = opts.handle().err().unwrap();
+        let res: serde_json::Value = serde_json::from_str(&error_response(&err, None)).unwrap();
+
+        assert_eq!(res["code"], 4);
+        assert_eq!(res["msg"], "CNI_NETNS is not set");
+    }
+
+    #[test]
+    fn opts_get_test() {
+        let input = r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24"}"#;
+        let opts = Opts::from_vars(input.as_bytes(), |key| match key {
+            "CNI_COMMAND" => Some("GET".to_string()),
+            _ => None,
+        })
+        .unwrap();
+
+        let cni_version = opts.cni_version().map(String::from);
+        let err = opts.handle().err().unwrap();
+        let res: serde_json::Value =
+            serde_json::from_str(&error_response(&err, cni_version.as_deref())).unwrap();
+
+        assert_eq!(res["cniVersion"], "0.4.0");
+        assert_eq!(res["code"], 4);
+        assert_eq!(res["msg"], "unsupported CNI command: GET");
+    }
+
+    #[test]
+    fn config_bridge_test() {
+        let input = r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24"}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert_eq!(config.bridge, "cni0");
+
+        let input = r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24", "bridge": "cni1"}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert_eq!(config.bridge, "cni1");
+    }
+
+    #[test]
+    fn config_subnet6_test() {
+        let input = r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24"}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert_eq!(config.subnet6, None);
+
+        let input = r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24", "subnet6": "fd00:10:244::/64"}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert_eq!(config.subnet6.as_deref(), Some("fd00:10:244::/64"));
+
+        let input = r#"{"cniVersion": "0.3.1", "name": "comet", "subnet": "10.244.0.0/24", "subnet6": "10.245.0.0/24"}"#;
+        let err = Config::from(input.as_bytes()).err().unwrap();
+
+        assert_eq!(err.to_string(), "invalid subnet6: 10.245.0.0/24");
+    }
+
+    #[test]
+    fn config_prev_result_test() {
+        let input = r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24"}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert!(config.prev_result.is_none());
+
+        let input = r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24", "prevResult": {"cniVersion": "0.4.0", "interfaces": [{"name": "bw0"}]}}"#;
+        let config = Config::from(input.as_bytes()).unwrap();
+
+        assert_eq!(config.prev_result.unwrap()["interfaces"][0]["name"], "bw0");
+    }
+
+    #[test]
+    fn opts_del_prev_result_test() {
+        let data_dir = "/tmp/opts_del_prev_result_test";
+        let _ = fs::remove_dir_all(data_dir);
+        fs::create_dir_all(data_dir).unwrap();
+        fs::write(format!("{data_dir}/reserved_ips"), "10.244.0.2\n").unwrap();
+
+        let mut vars = add_vars();
+        vars.insert("CNI_COMMAND".to_string(), "DEL".to_string());
+
+        let input = r#"{"cniVersion": "0.4.0", "name": "comet", "subnet": "10.244.0.0/24", "ipam": {"dataDir": "/tmp/opts_del_prev_result_test"}, "prevResult": {"cniVersion": "0.4.0", "interfaces": [{"name": "eth0"}], "ips": [{"version": "4", "address": "10.244.0.2/24", "interface": 0}]}}"#;
+        let opts = Opts::from_vars(input.as_bytes(), |key| vars.get(key).cloned()).unwrap();
+
+        assert_eq!(opts.handle().unwrap(), "");
+        assert!(fs::read_to_string(format!("{data_dir}/reserved_ips"))
+            .unwrap()
+            .is_empty());
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn config_data_dir_test() {
+        let data_dir = "/tmp/config_data_dir_test";
+        let _ = fs::remove_dir_all(data_dir);
+
+        let input = r#"
+        {
+            "cniVersion": "0.3.1",
+            "name": "comet",
+            "type": "comet-cni",
+            "subnet": "10.244.0.0/24",
+            "ipam": {
+                "dataDir": "/tmp/config_data_dir_test"
+            }
+        }
+        "#
+        .as_bytes();
+
+        let config = Config::from(input).unwrap();
+        let ip_store_path = config.ip_store_path();
+
+        assert_eq!(ip_store_path, format!("{data_dir}/reserved_ips"));
+
+        allocate_ip(&config.subnet, "123456789", None, &ip_store_path).unwrap();
+
+        assert!(Path::new(&ip_store_path).exists());
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn error_response_test() {
+        let err = anyhow!("no free addresses in subnet 10.244.0.0/29");
+        let res: serde_json::Value = serde_json::from_str(&error_response(&err, None)).unwrap();
+
+        assert_eq!(res["code"], 7);
+        assert_eq!(res["msg"], "no free addresses in subnet 10.244.0.0/29");
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let args = parse_args("IgnoreUnknown=1;IP=10.244.0.50;K8S_POD_NAME=nginx");
+
+        assert_eq!(args.len(), 3);
+        assert_eq!(args["IP"], "10.244.0.50");
+        assert_eq!(args["K8S_POD_NAME"], "nginx");
+
+        assert!(parse_args("").is_empty());
+    }
+
+    #[test]
+    fn requested_ip_test() {
+        let ip = requested_ip(&parse_args("IP=10.244.0.50")).unwrap();
+        assert_eq!(ip, Some("10.244.0.50".parse().unwrap()));
+
+        assert_eq!(requested_ip(&parse_args("")).unwrap(), None);
+
+        let err = requested_ip(&parse_args("IP=fd00::50")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "requested IP fd00::50 is IPv6, only IPv4 addresses can be requested"
+        );
+    }
+
+    #[test]
+    fn run_error_test() {
+        let vars = add_vars();
+
+        let input = r#"
+        {
+            "cniVersion": "0.3.1",
+            "name": "comet",
+            "type": "comet-cni",
+            "subnet": "10.244.0.0/33"
+        }
+        "#
+        .as_bytes();
+
+        let err = Opts::from_vars(input, |key| vars.get(key).cloned())
+            .and_then(run)
+            .err()
+            .unwrap();
+        let res: serde_json::Value = serde_json::from_str(&error_response(&err, None)).unwrap();
+
+        assert_eq!(res["cniVersion"], "0.3.1");
+        assert_eq!(res["code"], 7);
+        assert_eq!(res["msg"], "invalid subnet: 10.244.0.0/33");
+        assert!(res["details"].is_string());
+    }
+
+    #[test]
+    fn config_unsupported_version_test() {
+        let input = r#"
+        {
+            "cniVersion": "1.0.0",
+            "name": "comet",
+            "type": "comet-cni",
+            "subnet": "10.244.0.0/24"
+        }
+        "#
+        .as_bytes();
+
+        let err = Config::from(input).err().unwrap();
+
+        assert_eq!(err.to_string(), "unsupported CNI version: 1.0.0");
+    }
+
+    #[test]
+    fn opts_pod_test() {
+        let mut vars = add_vars();
+        vars.insert(
+            "CNI_ARGS".to_string(),
+            "IgnoreUnknown=1;K8S_POD_NAMESPACE=default;K8S_POD_NAME=nginx;K8S_POD_INFRA_CONTAINER_ID=123456789".to_string(),
+        );
+
+        let input = r#"
+        {
+            "cniVersion": "0.3.1",
+            "name": "comet",
+            "type": "comet-cni",
+            "subnet": "10.244.0.0/24"
+        }
+        "#
+        .as_bytes();
+
+        let opts = Opts::from_vars(input, |key| vars.get(key).cloned()).unwrap();
+
+        assert_eq!(
+            opts.pod,
+            Some(Pod {
+                name: "nginx".to_string(),
+                namespace: "default".to_string(),
+                infra_container_id: Some("123456789".to_string()),
+            })
+        );
     }
 }
@@ -1,34 +1,275 @@
-use std::{fs, net::Ipv4Addr};
+use std::{
+    env, fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    process::Command,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use ipnet::{IpNet, Ipv6Net};
+use netlink::netlink::Netlink;
 use network::netlink::{add_addr, add_link, set_up};
 
 const BRIDGE_IF_NAME: &str = "cni0";
 const CNI_CONF_PATH: &str = "/etc/cni/net.d/08-comet.conf";
 const CNI_BIN_PATH: &str = "/opt/cni/bin/comet-cni";
+const IP_FORWARD_PATH: &str = "/proc/sys/net/ipv4/ip_forward";
+const IP6_FORWARD_PATH: &str = "/proc/sys/net/ipv6/conf/all/forwarding";
+const POD_NETWORK_ENV: &str = "COMET_POD_NETWORK";
+// optional IPv6 subnet of this node's pods, passed on as the network's subnet6
+const POD_SUBNET6_ENV: &str = "COMET_POD_SUBNET6";
+const DEFAULT_POD_NETWORK: &str = "10.244.0.0/16";
+// reserved-IP store of the "comet" network, see comet-cni's ipam allocator
+const IP_STORE_DIR: &str = "/var/lib/cni/comet/comet";
+
+// the installer runs on every DaemonSet restart, so everything here has to
+// tolerate the bridge being set up already
+fn setup_bridge(bridge_ip: IpAddr, subnet_mask_size: &str) -> Result<()> {
+    let mut netlink = Netlink::new()?;
+
+    let exists = netlink
+        .link_list()?
+        .iter()
+        .any(|link| link.attrs().name == BRIDGE_IF_NAME);
+
+    if !exists {
+        add_link(BRIDGE_IF_NAME, "bridge")?;
+    }
 
-fn setup_bridge(bridge_ip: Ipv4Addr, subnet_mask_size: &str) -> Result<()> {
-    add_link(BRIDGE_IF_NAME, "bridge")?;
     set_up(BRIDGE_IF_NAME)?;
-    add_addr(bridge_ip, subnet_mask_size, BRIDGE_IF_NAME)?;
+
+    let bridge = netlink.link_get_by_name(BRIDGE_IF_NAME)?;
+    let bridge_net = IpNet::new(bridge_ip, subnet_mask_size.parse()?)?;
+
+    let has_addr = netlink
+        .addr_show(&bridge)?
+        .iter()
+        .any(|addr| addr.ip == bridge_net);
+
+    if !has_addr {
+        add_addr(bridge_ip, subnet_mask_size, BRIDGE_IF_NAME)?;
+    }
+
+    Ok(())
+}
+
+const NET_CONF: &str = r#"{
+    "cniVersion": "0.3.1",
+    "name": "comet",
+    "type": "comet-cni",
+    "network": "10.244.0.0/16",
+    "subnet": "10.244.0.0/24"
+}"#;
+
+// the pods' IPv6 gateway lives on the bridge, comet-cni's allocator hands out
+// the first address after the network for it
+fn setup_bridge6(subnet6: &str) -> Result<()> {
+    let net = subnet6.parse::<Ipv6Net>()?;
+    let gw_ip = Ipv6Addr::from(u128::from(net.network()) + 1);
+
+    setup_bridge(gw_ip.into(), &net.prefix_len().to_string())
+}
+
+fn net_conf(subnet6: Option<&str>) -> Result<String> {
+    let mut conf: serde_json::Value = serde_json::from_str(NET_CONF)?;
+
+    if let Some(subnet6) = subnet6 {
+        conf["subnet6"] = subnet6.into();
+    }
+
+    Ok(serde_json::to_string_pretty(&conf)?)
+}
+
+fn enable_ip_forward() -> Result<()> {
+    fs::write(IP_FORWARD_PATH, "1")?;
+
+    Ok(())
+}
+
+fn enable_ip6_forward() -> Result<()> {
+    fs::write(IP6_FORWARD_PATH, "1")?;
+
+    Ok(())
+}
+
+// traffic leaving the pod network is masqueraded, traffic between pods is not
+fn setup_masquerade(pod_network: &str) -> Result<()> {
+    let rule = [
+        "POSTROUTING",
+        "-s",
+        pod_network,
+        "!",
+        "-d",
+        pod_network,
+        "-j",
+        "MASQUERADE",
+    ];
+
+    let out = Command::new("iptables")
+        .args(["-t", "nat", "-C"])
+        .args(rule)
+        .output()?;
+
+    if out.status.success() {
+        return Ok(());
+    }
+
+    let out = Command::new("iptables")
+        .args(["-t", "nat", "-A"])
+        .args(rule)
+        .output()?;
+
+    if !out.status.success() {
+        bail!(
+            "Failed to add masquerade rule: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+fn uninstall(conf_path: &str, ip_store_dir: Option<&str>) -> Result<()> {
+    let mut netlink = Netlink::new()?;
+
+    if let Some(bridge) = netlink
+        .link_list()?
+        .into_iter()
+        .find(|link| link.attrs().name == BRIDGE_IF_NAME)
+    {
+        netlink.link_del(&bridge)?;
+    }
+
+    remove(fs::remove_file(conf_path))?;
+
+    if let Some(ip_store_dir) = ip_store_dir {
+        remove(fs::remove_dir_all(ip_store_dir))?;
+    }
 
     Ok(())
 }
 
+// uninstalling is repeatable, so anything that is already gone is fine
+fn remove(res: io::Result<()>) -> Result<()> {
+    match res {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
 fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.iter().any(|arg| arg == "--uninstall") {
+        let flush_ips = args.iter().any(|arg| arg == "--flush-ips");
+
+        uninstall(CNI_CONF_PATH, flush_ips.then_some(IP_STORE_DIR)).unwrap();
+        return;
+    }
+
     let bridge_ip = Ipv4Addr::new(10, 244, 0, 1);
     let subnet_mask_size = "24";
 
-    setup_bridge(bridge_ip, subnet_mask_size).unwrap();
+    setup_bridge(bridge_ip.into(), subnet_mask_size).unwrap();
+
+    let pod_network = env::var(POD_NETWORK_ENV).unwrap_or_else(|_| DEFAULT_POD_NETWORK.to_string());
+    let subnet6 = env::var(POD_SUBNET6_ENV).ok();
 
-    let net_conf = r#"{
-        "cniVersion": "0.3.1",
-        "name": "comet",
-        "type": "comet-cni",
-        "network": "10.244.0.0/16",
-        "subnet": "10.244.0.0/24"
-    }"#;
+    enable_ip_forward().unwrap();
+    setup_masquerade(&pod_network).unwrap();
+
+    if let Some(subnet6) = &subnet6 {
+        setup_bridge6(subnet6).unwrap();
+        enable_ip6_forward().unwrap();
+    }
 
     fs::copy("comet-cni", CNI_BIN_PATH).unwrap();
-    fs::write(CNI_CONF_PATH, net_conf).unwrap();
+    fs::write(CNI_CONF_PATH, net_conf(subnet6.as_deref()).unwrap()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::Ipv4Addr, path::Path};
+
+    use network::{run_command, test_setup};
+
+    use crate::{
+        enable_ip_forward, net_conf, setup_bridge, setup_bridge6, uninstall, BRIDGE_IF_NAME,
+        IP_FORWARD_PATH, NET_CONF,
+    };
+
+    #[test]
+    fn setup_bridge_twice_test() {
+        test_setup!();
+        let bridge_ip = Ipv4Addr::new(10, 244, 0, 1);
+
+        setup_bridge(bridge_ip.into(), "24").unwrap();
+        setup_bridge(bridge_ip.into(), "24").unwrap();
+
+        let out = run_command!("ip", "addr", "show", BRIDGE_IF_NAME);
+        let out = String::from_utf8_lossy(&out.stdout);
+
+        assert_eq!(out.matches("10.244.0.1/24").count(), 1);
+    }
+
+    #[test]
+    fn setup_bridge6_test() {
+        test_setup!();
+
+        setup_bridge(Ipv4Addr::new(10, 244, 0, 1).into(), "24").unwrap();
+        setup_bridge6("fd00:10:244::/64").unwrap();
+        setup_bridge6("fd00:10:244::/64").unwrap();
+
+        let out = run_command!("ip", "-6", "addr", "show", BRIDGE_IF_NAME);
+        let out = String::from_utf8_lossy(&out.stdout);
+
+        assert_eq!(out.matches("fd00:10:244::1/64").count(), 1);
+    }
+
+    #[test]
+    fn net_conf_test() {
+        let conf: serde_json::Value = serde_json::from_str(&net_conf(None).unwrap()).unwrap();
+
+        assert_eq!(conf["subnet"], "10.244.0.0/24");
+        assert!(conf.get("subnet6").is_none());
+
+        let conf: serde_json::Value =
+            serde_json::from_str(&net_conf(Some("fd00:10:244::/64")).unwrap()).unwrap();
+
+        assert_eq!(conf["subnet6"], "fd00:10:244::/64");
+    }
+
+    #[test]
+    fn enable_ip_forward_test() {
+        test_setup!();
+
+        enable_ip_forward().unwrap();
+
+        assert_eq!(fs::read_to_string(IP_FORWARD_PATH).unwrap().trim(), "1");
+    }
+
+    #[test]
+    fn uninstall_test() {
+        test_setup!();
+        let conf_path = "/tmp/08-comet-uninstall-test.conf";
+        let ip_store_dir = "/tmp/comet-uninstall-test";
+
+        setup_bridge(Ipv4Addr::new(10, 244, 0, 1).into(), "24").unwrap();
+        fs::write(conf_path, NET_CONF).unwrap();
+        fs::create_dir_all(ip_store_dir).unwrap();
+        fs::write(
+            format!("{ip_store_dir}/reserved_ips"),
+            "123456789 10.244.0.2\n",
+        )
+        .unwrap();
+
+        uninstall(conf_path, Some(ip_store_dir)).unwrap();
+
+        let out = run_command!("ip", "link", "show", BRIDGE_IF_NAME);
+        assert!(!out.status.success());
+        assert!(!Path::new(conf_path).exists());
+        assert!(!Path::new(ip_store_dir).exists());
+
+        // nothing left to remove the second time around
+        uninstall(conf_path, Some(ip_store_dir)).unwrap();
+    }
 }